@@ -0,0 +1,78 @@
+use crate::db::{self, ListFilter, ListOptions};
+use anyhow::Error;
+use rusqlite::Connection;
+use std::io;
+use std::result;
+use std::time::{Duration, Instant};
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = f();
+    (value, start.elapsed())
+}
+
+fn seed(conn: &Connection, tasks: usize) -> result::Result<(), Error> {
+    let tx = conn.unchecked_transaction()?;
+    for i in 0..tasks {
+        let tags = (i % 3 == 0).then(|| "work".to_string());
+        db::add_task(
+            &tx,
+            &format!("Task {i}"),
+            Some(i as i64 % 5 + 1),
+            None,
+            tags,
+            None,
+            None,
+        )?;
+    }
+    Ok(tx.commit()?)
+}
+
+/// Times the common operations against a fresh in-memory database of `tasks` tasks.
+pub fn run_benchmark(tasks: usize) -> result::Result<Vec<(&'static str, Duration)>, Error> {
+    let conn = db::open_in_memory()?;
+
+    let (seeded, insert) = time(|| seed(&conn, tasks));
+    seeded?;
+
+    let all = ListOptions::default().filter(ListFilter::All);
+    let (_, list) = time(|| db::list_tasks(&conn, &all, &mut io::sink()));
+    let (next, next_task) = time(|| db::next_batch(&conn, 1));
+    next?;
+    let (grouped, by_tag) = time(|| db::tasks_grouped_by_tag(&conn));
+    grouped?;
+
+    Ok(vec![
+        ("insert", insert),
+        ("list_tasks", list),
+        ("select_next_task", next_task),
+        ("tasks_grouped_by_tag", by_tag),
+    ])
+}
+
+pub fn print_benchmark(tasks: usize, csv: bool) {
+    let timings = match run_benchmark(tasks) {
+        Ok(timings) => timings,
+        Err(err) => return println!("{:?}", err),
+    };
+
+    match csv {
+        true => println!("operation,tasks,micros"),
+        false => println!("{:<22} {:>12}", format!("OPERATION ({tasks})"), "µs"),
+    }
+
+    for (operation, duration) in timings {
+        match csv {
+            true => println!("{operation},{tasks},{}", duration.as_micros()),
+            false => println!("{operation:<22} {:>12}", duration.as_micros()),
+        }
+    }
+}
+
+#[test]
+fn test_benchmark_1000_tasks() {
+    let (timings, total) = time(|| run_benchmark(1000).unwrap());
+
+    assert_eq!(timings.len(), 4);
+    assert!(total < Duration::from_secs(1), "took {total:?}");
+}
@@ -1,9 +1,40 @@
-use crate::db;
-use clap::{Parser, Subcommand};
+use crate::bench;
+use crate::config::{self, Config};
+use crate::date::{
+    day_bounds, parse_input_date, parse_input_date_after, parse_input_date_before,
+    parse_input_date_end,
+};
+use crate::db::{
+    self, Column, DisplayFormat, DueFormat, ListFilter, ListOptions, NotesFilter,
+    SelectionStrategy, Status,
+};
+use crate::export::{self, ExportFormat};
+use crate::output;
+use crate::parse::parse_id_range;
+use crate::prompt::{confirm, confirm_default_yes, optional, prompt};
+use crate::suggest::closest_command;
+use crate::tui;
+use crate::watch;
+use chrono::Utc;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{Shell, generate};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(version, about = "A _very_ simple task management cli")]
 struct Cli {
+    #[arg(long, global = true)]
+    /// Disable colored output, also respects NO_COLOR
+    no_color: bool,
+
+    #[arg(long, global = true)]
+    /// Print what add, done or migrate would do without changing the database
+    dry_run: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -13,38 +44,294 @@ enum Commands {
     #[clap(alias("a"))]
     /// Add a new task
     Add {
+        #[arg(required_unless_present = "template")]
         /// The actual task, wrap sentences in quotes
-        task: String,
+        task: Option<String>,
+
+        #[arg(long, value_name = "ID")]
+        /// Copy text, priority, tags and estimate from this task, unless given
+        template: Option<i64>,
 
         #[arg(short, long)]
-        /// Priority [1, 5]. Default 3.
+        /// Priority [1, 5]. Default 3, or TD_DEFAULT_PRIORITY if set.
         priority: Option<i64>,
 
+        #[arg(long, value_name = "SYMBOL", value_parser = db::parse_priority_symbol, conflicts_with = "priority")]
+        /// Priority as shown in listings, one of . - ~ ! !!!
+        priority_symbol: Option<i64>,
+
         #[arg(short, long)]
         // Due date
         due: Option<String>,
+
+        #[arg(short, long)]
+        /// Comma separated list of tags, e.g. "work,urgent"
+        tags: Option<String>,
+
+        #[arg(long)]
+        /// Who the task is assigned to
+        assignee: Option<String>,
+
+        #[arg(short, long, value_name = "MINUTES")]
+        /// How long the task is expected to take
+        estimate: Option<i64>,
     },
 
+    /// Add a new task, prompting for each field
+    Describe,
+
     #[clap(alias("l"))]
     /// List current tasks
     List {
-        #[arg(short, long)]
+        #[arg(long, group = "filter")]
+        /// List pending, in progress and blocked tasks. This is the default
+        active: bool,
+
+        #[arg(short, long, group = "filter")]
         /// List all tasks, including completed and cancelled
         all: bool,
 
-        #[arg(long, conflicts_with = "all")]
+        #[arg(long, group = "filter")]
         /// List completed tasks
         completed: bool,
+
+        #[arg(long, group = "filter")]
+        /// List only pending tasks, leaving out in progress and blocked ones
+        pending_only: bool,
+
+        #[arg(long, group = "filter")]
+        /// List only tasks that have not been started, same as --pending-only
+        no_in_progress: bool,
+
+        #[arg(long, group = "filter")]
+        /// List only in progress tasks
+        in_progress: bool,
+
+        #[arg(long, group = "filter")]
+        /// List only blocked tasks
+        blocked: bool,
+
+        #[arg(
+            long,
+            group = "filter",
+            conflicts_with_all = [
+                "reverse", "no_overdue_section", "mine", "since", "until", "created_after",
+                "created_before", "since_last_gc", "priority", "due", "has_notes", "no_notes",
+                "min_age_days", "max_age_days", "limit", "from_id", "today_due_first",
+                "with_id_range", "format", "line", "with_scores", "fields", "with_age",
+                "due_format", "width", "ndjson", "json", "export_sql", "export_json_schema",
+                "fail_on_empty", "ascii",
+            ]
+        )]
+        /// List archived tasks
+        archived: bool,
+
+        #[arg(short, long)]
+        /// Reverse the sort order
+        reverse: bool,
+
+        #[arg(long)]
+        /// Don't pull overdue tasks into a separate section on top
+        no_overdue_section: bool,
+
+        #[arg(long, visible_alias = "tags", conflicts_with = "filter")]
+        /// Show active tasks in one section per tag
+        group_by_tags: bool,
+
+        #[arg(long, conflicts_with_all = ["filter", "group_by_tags"])]
+        /// Show all tasks in one section per status
+        group_by_status: bool,
+
+        #[arg(long, requires = "group_by_status")]
+        /// Also show statuses without any tasks
+        show_empty_groups: bool,
+
+        #[arg(long)]
+        /// Only list tasks assigned to you, set TD_USER or `td config set user` to configure who you are
+        mine: bool,
+
+        #[arg(long, value_name = "DATE")]
+        /// Only list tasks created on or after DATE
+        since: Option<String>,
+
+        #[arg(long, value_name = "DATE")]
+        /// Only list tasks created on or before DATE
+        until: Option<String>,
+
+        #[arg(long, value_name = "DATE", conflicts_with = "since")]
+        /// Only list tasks created after DATE, excluding DATE itself
+        created_after: Option<String>,
+
+        #[arg(long, value_name = "DATE", conflicts_with = "until")]
+        /// Only list tasks created before DATE, excluding DATE itself
+        created_before: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["since", "created_after"])]
+        /// Only list tasks created after `td gc` last ran, all tasks if it never has
+        since_last_gc: bool,
+
+        #[arg(long, value_parser = db::parse_priority)]
+        /// Only list tasks of this priority, as a number or a symbol like `!`
+        priority: Option<i64>,
+
+        #[arg(long, group = "due")]
+        /// Only list tasks without a due date
+        due_null: bool,
+
+        #[arg(long, conflicts_with = "no_notes")]
+        /// Only list tasks with notes
+        has_notes: bool,
+
+        #[arg(long)]
+        /// Only list tasks without notes
+        no_notes: bool,
+
+        #[arg(long, group = "due")]
+        /// Only list tasks due within the next 7 days, starting today
+        due_this_week: bool,
+
+        #[arg(long, group = "due")]
+        /// Only list tasks due 7 to 14 days from today
+        due_next_week: bool,
+
+        #[arg(long, value_name = "DAYS")]
+        /// Only list tasks created at least DAYS ago
+        min_age_days: Option<u32>,
+
+        #[arg(long, value_name = "DAYS", num_args = 0..=1, conflicts_with_all = ["filter", "min_age_days"])]
+        /// Only list pending tasks created at least DAYS ago, defaults to TD_STALE_PENDING_DAYS or 7
+        stale: Option<Option<u32>>,
+
+        #[arg(long, value_name = "DAYS")]
+        /// Only list tasks created at most DAYS ago
+        max_age_days: Option<u32>,
+
+        #[arg(short = 'n', long)]
+        /// Show at most this many tasks
+        limit: Option<usize>,
+
+        #[arg(long, value_name = "ID")]
+        /// Only show tasks after this id, for paging with --all and --limit
+        from_id: Option<i64>,
+
+        #[arg(long, conflicts_with = "reverse")]
+        /// Show tasks due today first, then the rest by priority
+        today_due_first: bool,
+
+        #[arg(long, value_name = "FROM-TO", value_parser = parse_id_range)]
+        /// Only show tasks with ids from FROM to TO, e.g. 5-10
+        with_id_range: Option<(i64, i64)>,
+
+        #[arg(long, value_enum, default_value_t = DisplayFormat::Table)]
+        /// How to lay out each task
+        format: DisplayFormat,
+
+        #[arg(long, conflicts_with = "format")]
+        /// Fit each task on one line without padding, same as --format line
+        line: bool,
+
+        #[arg(long, conflicts_with_all = ["format", "line", "json", "ndjson", "export_sql"])]
+        /// Append the score of `td score` to each pending task
+        with_scores: bool,
+
+        #[arg(long, value_name = "FIELDS", value_parser = parse_fields)]
+        /// Comma separated columns of the table, e.g. id,task,priority
+        fields: Option<::std::vec::Vec<Column>>,
+
+        #[arg(long)]
+        /// Add an AGE column with the days since each task was created
+        with_age: bool,
+
+        #[arg(long, value_enum, default_value_t = DueFormat::Absolute)]
+        /// How to show dates in the DUE column
+        due_format: DueFormat,
+
+        #[arg(long, value_name = "N")]
+        /// Truncate rows to N characters, defaults to the terminal width
+        width: Option<usize>,
+
+        #[arg(long, visible_alias = "jsonl")]
+        /// Print one JSON object per task and line
+        ndjson: bool,
+
+        #[arg(long, conflicts_with = "ndjson")]
+        /// Print the tasks as a JSON array
+        json: bool,
+
+        #[arg(long, requires = "json")]
+        /// Indent the JSON output, only valid with --json
+        pretty: bool,
+
+        #[arg(long, conflicts_with_all = ["ndjson", "json"])]
+        /// Print an SQL INSERT statement per task
+        export_sql: bool,
+
+        #[arg(short, long)]
+        /// Write the list to a file instead of stdout
+        output: Option<PathBuf>,
+
+        #[arg(long)]
+        /// Print the JSON Schema of the tasks in --json and --ndjson output
+        export_json_schema: bool,
+
+        #[arg(long, conflicts_with_all = ["archived", "group_by_tags"])]
+        /// Only print the number of tasks, exiting with 1 when there are none
+        count_only: bool,
+
+        #[arg(long, visible_alias = "zero-exit-on-empty")]
+        /// Exit with 1 when no tasks are listed
+        fail_on_empty: bool,
+
+        #[arg(short, long, conflicts_with_all = ["archived", "group_by_tags", "output"])]
+        /// Browse and act on tasks in an interactive view
+        interactive: bool,
+
+        #[arg(long, conflicts_with_all = ["interactive", "json", "ndjson", "export_sql"])]
+        /// Replace symbols and other non-ASCII characters, for terminals without Unicode
+        ascii: bool,
+
+        #[arg(long, group = "status_count", conflicts_with = "filter")]
+        /// Only print the number of pending tasks
+        pending_count: bool,
+
+        #[arg(long, group = "status_count", conflicts_with = "filter")]
+        /// Only print the number of in progress tasks
+        in_progress_count: bool,
+
+        #[arg(long, group = "status_count", conflicts_with = "filter")]
+        /// Only print the number of completed tasks
+        completed_count: bool,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["filter", "status_count", "group_by_tags", "group_by_status", "count_only", "interactive"]
+        )]
+        /// Only print how many tasks are pending, in progress and overdue
+        summary_only: bool,
     },
 
+    #[clap(alias("f"))]
+    /// List in progress tasks, shorthand for `list --in-progress`
+    Focus,
+
     #[clap(alias("d"))]
     /// Mark a task as complete
     Done {
-        id: i64,
+        #[arg(required = true, value_parser = parse_ids)]
+        /// One or more tasks to complete, or ranges of them like 5-10
+        ids: Vec<::std::vec::Vec<i64>>,
 
         #[arg(short, long)]
         /// Also promote next task to "In Progress"
         next: bool,
+
+        #[arg(long)]
+        /// Record a note on why or how the task was completed
+        note: Option<String>,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt when completing several tasks
+        yes: bool,
     },
 
     #[clap(alias("n"))]
@@ -52,15 +339,52 @@ enum Commands {
     Next {
         #[arg(short, long)]
         id: Option<i64>,
+
+        #[arg(short, long, conflicts_with = "id")]
+        /// Promote this many tasks at once
+        count: Option<usize>,
+
+        #[arg(long, value_enum, conflicts_with_all = ["id", "count"])]
+        /// How to pick the task, defaults to TD_NEXT_STRATEGY or priority
+        strategy: Option<SelectionStrategy>,
     },
 
+    /// Copy a task, typically a completed one, into a new in progress task
+    Repeat { id: i64 },
+
+    /// Pick a random pending task as the next active task
+    Random,
+
     #[clap(alias("s"))]
     /// Show current active task
-    Show,
+    Show {
+        #[arg(short, long)]
+        /// Show this task instead of the active one
+        id: Option<i64>,
+
+        #[arg(long, value_enum, value_name = "FIELD")]
+        /// Print only this field of the task, e.g. for a shell prompt
+        field: Option<Column>,
+    },
+
+    /// Show the most recently added task
+    Last {
+        #[arg(short, long)]
+        /// Also set the task to in progress
+        start: bool,
+    },
 
     #[clap(alias("p"))]
     /// Pause current task
-    Pause,
+    Pause {
+        #[arg(short, long)]
+        /// Pause every in progress task
+        all: bool,
+
+        #[arg(short, long, requires = "all")]
+        /// Skip the confirmation prompt
+        force: bool,
+    },
 
     #[clap(alias("c"))]
     /// Cancel a task
@@ -68,30 +392,761 @@ enum Commands {
         id: i64,
 
         #[arg(short, long)]
-        // Hard delete task on cancel
+        // Delete task on cancel, see `td gc`
         delete: bool,
+
+        #[arg(short, long)]
+        /// Why the task was cancelled, appended to the task notes
+        reason: Option<String>,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    /// Permanently delete a task right away
+    Delete {
+        id: i64,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    #[clap(alias("b"))]
+    /// Mark a task as blocked, waiting on external input
+    Block {
+        id: i64,
+
+        #[arg(short, long)]
+        /// What the task is waiting on, stored in the task notes
+        reason: Option<String>,
+    },
+
+    /// Append a line to the notes of a task
+    Note {
+        id: i64,
+
+        /// The line to add, wrap sentences in quotes
+        text: String,
+    },
+
+    /// Show overdue, due today and in progress tasks
+    Daily {
+        #[arg(long)]
+        /// Print the agenda as JSON
+        json: bool,
+
+        #[arg(short, long)]
+        /// Write the agenda to a file instead of stdout
+        output: Option<PathBuf>,
+    },
+
+    /// Keep the task list on screen, printing it again when the tasks change
+    Watch {
+        #[arg(long, default_value_t = 1000)]
+        /// How often to check for changes, in milliseconds
+        interval_ms: u64,
+    },
+
+    #[clap(visible_alias("weight"))]
+    /// Rank pending tasks by a score weighing priority, due date and age
+    Score,
+
+    /// Show the share of completed tasks, overall and per tag
+    Progress {
+        #[arg(long)]
+        /// Print the report as JSON
+        json: bool,
+    },
+
+    /// Show how active tasks are distributed across priorities
+    Hist {
+        #[arg(short, long)]
+        /// Include tasks of all statuses
+        all: bool,
+    },
+
+    /// Change the due date of a task
+    Due {
+        id: i64,
+
+        /// The new due date, formatted as dd.mm.yyyy
+        date: String,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    /// Assign a task to someone
+    Assign { id: i64, assignee: String },
+
+    /// Rename a tag on every task
+    RenameTag { old: String, new: String },
+
+    /// Remove a tag from every task
+    RemoveTag { tag: String },
+
+    /// Export all tasks in another task manager's format
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Todotxt)]
+        /// The format to write
+        format: ExportFormat,
+
+        #[arg(long)]
+        /// Export the archive of `td archive` instead of the task list
+        archived: bool,
+
+        #[arg(short, long)]
+        /// Write the export to a file instead of stdout
+        output: Option<PathBuf>,
+    },
+
+    /// Add the tasks in a todo.txt file
+    Import {
+        /// The todo.txt file to read
+        file: PathBuf,
+    },
+
+    /// Move completed tasks out of the task list into an archive
+    Archive {
+        #[arg(long)]
+        /// Archive cancelled tasks as well
+        include_cancelled: bool,
     },
 
     /// Delete cancelled tasks
-    Gc,
+    Gc {
+        #[arg(long, value_name = "DAYS")]
+        /// Instead delete completed and cancelled tasks finished more than DAYS ago
+        older_than: Option<i64>,
+
+        #[arg(long, conflicts_with_all = ["older_than", "restore"])]
+        /// Permanently remove deleted tasks
+        purge: bool,
+
+        #[arg(long, value_name = "ID", conflicts_with = "older_than")]
+        /// Bring back a deleted task
+        restore: Option<i64>,
+
+        #[arg(short, long, conflicts_with = "restore")]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    /// Permanently remove completed tasks
+    PurgeCompleted {
+        #[arg(long, value_name = "DAYS")]
+        /// Only remove tasks completed more than DAYS ago
+        older_than: Option<u32>,
+
+        #[arg(short, long)]
+        /// Skip the confirmation prompt
+        yes: bool,
+    },
+
+    /// Initialize the database and print where it lives
+    Init {
+        #[arg(long)]
+        /// Drop all tasks and recreate the database
+        reinit: bool,
+
+        #[arg(long, requires = "reinit")]
+        /// Skip the confirmation prompt of --reinit
+        force: bool,
+    },
+
+    /// Apply pending database schema migrations, --dry-run prints them instead
+    Migrate,
+
+    /// Read or change settings in ~/.config/td/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    #[clap(hide = true)]
+    /// Time common queries against an in-memory database
+    Benchmark {
+        #[arg(default_value_t = 1000)]
+        /// How many tasks to seed the database with
+        tasks: usize,
+
+        #[arg(long)]
+        /// Print the timings as CSV
+        csv: bool,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        /// Defaults to the shell in $SHELL
+        shell: Option<Shell>,
+
+        #[arg(long)]
+        /// Write the script to the completions directory of the shell
+        install: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Save a setting to the config file
+    Set { key: String, value: String },
+    /// Print a setting, including TD_* environment overrides
+    Get { key: String },
+}
+
+fn open_output(path: Option<PathBuf>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => {
+            // Escape codes don't belong in files
+            output::disable_color();
+            Box::new(File::create(path)?)
+        }
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// Columns for `list --fields`. The task text is unpadded, so it is moved last.
+fn parse_fields(s: &str) -> Result<Vec<Column>, String> {
+    let mut columns = s
+        .split(',')
+        .map(|name| {
+            Column::from_str(name.trim(), true).map_err(|_| {
+                let valid: Vec<_> = Column::value_variants()
+                    .iter()
+                    .filter_map(|column| column.to_possible_value())
+                    .map(|value| value.get_name().to_string())
+                    .collect();
+                format!(
+                    "Unknown field \"{name}\", expected one of {}",
+                    valid.join(", ")
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !columns.contains(&Column::Id) || !columns.contains(&Column::Task) {
+        return Err("Fields must include id and task".to_string());
+    }
+    for (i, column) in columns.iter().enumerate() {
+        if columns[..i].contains(column) {
+            let name = column
+                .to_possible_value()
+                .map(|value| value.get_name().to_string());
+            return Err(format!(
+                "Field \"{}\" is listed twice",
+                name.unwrap_or_default()
+            ));
+        }
+    }
+
+    columns.sort_by_key(|&column| column == Column::Task);
+    Ok(columns)
+}
+
+/// Ranges are expanded into ids before touching the database, so a typo like
+/// `1-1000000000` must not allocate billions of them.
+const MAX_ID_RANGE: i64 = 10_000;
+
+/// A task id, or an inclusive range of them like `5-10`.
+fn parse_ids(s: &str) -> anyhow::Result<Vec<i64>> {
+    if let Ok(id) = s.parse::<i64>() {
+        return Ok(vec![id]);
+    }
+
+    let (from, to) = parse_id_range(s)?;
+    if to - from >= MAX_ID_RANGE {
+        anyhow::bail!("Ranges can span at most {MAX_ID_RANGE} ids, got \"{s}\"");
+    }
+    Ok((from..=to).collect())
+}
+
+/// Where each shell looks up completions for the current user.
+fn completions_path(shell: Shell) -> Option<PathBuf> {
+    let home = std::env::home_dir()?;
+
+    match shell {
+        Shell::Bash => Some(home.join(".local/share/bash-completion/completions/td")),
+        Shell::Zsh => Some(home.join(".zsh/completions/_td")),
+        Shell::Fish => Some(home.join(".config/fish/completions/td.fish")),
+        _ => None,
+    }
+}
+
+fn install_completions(shell: Shell) {
+    let Some(path) = completions_path(shell) else {
+        return println!(
+            "Installing is not supported for {shell}. Hint: use `td completions {shell}`"
+        );
+    };
+
+    // Creating the directory would not make the shell read it
+    if !path.parent().is_some_and(|dir| dir.is_dir()) {
+        return println!(
+            "{} does not exist. Hint: install manually with `td completions {shell} > <file>`",
+            path.parent().unwrap_or(&path).display()
+        );
+    }
+
+    match File::create(&path) {
+        Ok(mut file) => {
+            generate(shell, &mut Cli::command(), "td", &mut file);
+            println!("Installed {shell} completions to {}", path.display())
+        }
+        Err(err) => println!("{:?}", err),
+    }
+}
+
+/// Prompts for the fields of `add`: the task, priority, due date and tags.
+fn prompt_task(default_priority: i64) -> Option<(String, i64, Option<String>, Option<String>)> {
+    let task = prompt("Task", |answer| {
+        optional(answer).ok_or_else(|| "A task is required".to_string())
+    })?;
+
+    let priority = prompt(
+        &format!("Priority [1-5, {default_priority}]"),
+        |answer| match answer {
+            "" => Ok(default_priority),
+            _ => answer
+                .parse()
+                .ok()
+                .filter(|priority| (1..=5).contains(priority))
+                .ok_or_else(|| "Priority must be between 1 and 5".to_string()),
+        },
+    )?;
+
+    let due = prompt(
+        "Due date [dd.mm.yyyy, optional]",
+        |answer| match parse_input_date(answer) {
+            _ if answer.is_empty() => Ok(None),
+            Ok(_) => Ok(Some(answer.to_string())),
+            Err(_) => Err("Dates are written as dd.mm.yyyy".to_string()),
+        },
+    )?;
+
+    let tags = prompt("Tags [comma separated, optional]", |answer| {
+        Ok(optional(answer))
+    })?;
+
+    Some((task, priority, due, tags))
+}
+
+/// Like `Cli::parse`, but suggests the closest command when given an unknown one.
+fn parse_args() -> Cli {
+    let err = match Cli::try_parse() {
+        Ok(args) => return args,
+        Err(err) => err,
+    };
+
+    if err.kind() == ErrorKind::InvalidSubcommand
+        && let Some(ContextValue::String(input)) = err.get(ContextKind::InvalidSubcommand)
+    {
+        let cmd = Cli::command();
+        let names: Vec<&str> = cmd.get_subcommands().map(|c| c.get_name()).collect();
+
+        if let Some(name) = closest_command(input, &names) {
+            eprintln!("Unknown command '{input}'. Did you mean '{name}'?");
+            std::process::exit(2);
+        }
+    }
+
+    err.exit()
 }
 
 pub fn run() {
-    let args = Cli::parse();
+    let args = parse_args();
+
+    if args.no_color {
+        output::disable_color();
+    }
+
+    // Every other command migrates the database when opening it
+    if let Commands::Migrate = args.command {
+        return db::run_migrations(&db::open_db(), args.dry_run);
+    }
+
+    if args.dry_run && !matches!(args.command, Commands::Add { .. } | Commands::Done { .. }) {
+        eprintln!("--dry-run is only supported by add, done and migrate");
+        std::process::exit(2);
+    }
+
+    if let Commands::Config { action } = args.command {
+        let path = config::config_path();
+        let result = match action {
+            ConfigAction::Set { key, value } => Config::read(&path)
+                .and_then(|mut config| config.set(&key, &value).map(|_| config))
+                .and_then(|config| config.write(&path)),
+            ConfigAction::Get { key } => Config::load().get(&key).map(|value| println!("{value}")),
+        };
+        // Scripts reading settings need to tell a bad key from an empty value
+        if let Err(err) = result {
+            eprintln!("{:?}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Works on its own database
+    if let Commands::Benchmark { tasks, csv } = args.command {
+        return bench::print_benchmark(tasks, csv);
+    }
+
+    if let Commands::Completions { shell, install } = args.command {
+        let Some(shell) = shell.or_else(Shell::from_env) else {
+            return println!(
+                "Could not detect your shell. Hint: pass it, e.g. `td completions bash`"
+            );
+        };
+
+        return match install {
+            true => install_completions(shell),
+            false => generate(shell, &mut Cli::command(), "td", &mut io::stdout()),
+        };
+    }
+
     let conn = db::init_db();
+    let config = Config::load();
     let active = db::get_current_active_task(&conn);
 
+    if let Some(task) = &active {
+        db::warn_if_stale(task, config.stale_after_hours);
+    }
+
     match args.command {
         Commands::Add {
             task,
+            template: Some(source_id),
             priority,
+            priority_symbol,
             due,
-        } => db::add_task(&conn, &task, priority, due),
+            tags,
+            assignee,
+            estimate,
+        } => {
+            if args.dry_run {
+                return println!("[dry-run] Would: add task from template [{source_id}]");
+            }
 
-        Commands::List { all, completed } => db::list_tasks(&conn, all, completed),
+            let overrides = db::AddOverrides {
+                task,
+                priority: priority.or(priority_symbol),
+                due,
+                tags,
+                assignee,
+                estimate_minutes: estimate,
+            };
+            match db::add_from_template(&conn, source_id, overrides) {
+                Ok(id) => println!("✓ Added task [{id}] from template [{source_id}]"),
+                Err(err) => println!("{:?}", err),
+            }
+        }
 
-        Commands::Next { id } => match active {
-            None => db::select_next_task(&conn, id),
+        Commands::Add {
+            task: Some(task),
+            priority,
+            priority_symbol,
+            due,
+            tags,
+            assignee,
+            estimate,
+            ..
+        } => {
+            let priority = priority
+                .or(priority_symbol)
+                .or(Some(config.default_priority));
+            if args.dry_run {
+                return println!("[dry-run] Would: add task \"{task}\"");
+            }
+
+            match db::add_task(&conn, &task, priority, due, tags, assignee, estimate) {
+                Ok(id) => println!("✓ Added task [{id}] \"{task}\""),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        // Clap requires the task text when there is no template
+        Commands::Add { .. } => unreachable!(),
+
+        Commands::Describe => {
+            let Some((task, priority, due, tags)) = prompt_task(config.default_priority) else {
+                return println!("Aborted.");
+            };
+
+            match db::add_task(&conn, &task, Some(priority), due, tags, None, None) {
+                Ok(id) => println!("✓ Added task [{id}] \"{task}\""),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::List {
+            archived: true,
+            output,
+            ..
+        } => match open_output(output) {
+            Ok(mut out) => db::print_archived_tasks(&conn, &mut out),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::List {
+            pending_count,
+            in_progress_count,
+            completed_count,
+            ..
+        } if pending_count || in_progress_count || completed_count => {
+            let status = if pending_count {
+                Status::Pending
+            } else if in_progress_count {
+                Status::InProgress
+            } else {
+                Status::Completed
+            };
+
+            match db::count_by_status(&conn) {
+                Ok(counts) => println!("{}", counts.get(&status).unwrap_or(&0)),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::List {
+            group_by_status: true,
+            show_empty_groups,
+            output,
+            ..
+        } => match open_output(output) {
+            Ok(mut out) => db::list_tasks_by_status(&conn, show_empty_groups, &mut out),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::List {
+            group_by_tags: true,
+            output,
+            ..
+        } => match open_output(output) {
+            Ok(mut out) => db::list_tasks_grouped_by_tag(&conn, &mut out),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::List {
+            all,
+            completed,
+            pending_only,
+            no_in_progress,
+            in_progress,
+            blocked,
+            reverse,
+            no_overdue_section,
+            mine,
+            since,
+            until,
+            since_last_gc,
+            created_after,
+            created_before,
+            priority,
+            due_null,
+            has_notes,
+            no_notes,
+            due_this_week,
+            due_next_week,
+            min_age_days,
+            max_age_days,
+            stale,
+            limit,
+            from_id,
+            with_id_range,
+            today_due_first,
+            format,
+            line,
+            with_scores,
+            fields,
+            with_age,
+            due_format,
+            width,
+            ndjson,
+            json,
+            pretty,
+            export_sql,
+            export_json_schema,
+            output,
+            interactive,
+            ascii,
+            count_only,
+            fail_on_empty,
+            summary_only,
+            ..
+        } => {
+            // Listing everyone's tasks instead would look like they were all yours
+            if mine && config.user.is_none() {
+                eprintln!(
+                    "No user configured. Hint: set TD_USER or run `td config set user <NAME>`"
+                );
+                std::process::exit(1);
+            }
+
+            // The exclusive bounds map onto the inclusive ones a second further in
+            let since = match (since, created_after) {
+                (Some(date), _) => Some(parse_input_date(&date)),
+                (_, Some(date)) => Some(parse_input_date_after(&date)),
+                (None, None) => None,
+            };
+            let until = match (until, created_before) {
+                (Some(date), _) => Some(parse_input_date_end(&date)),
+                (_, Some(date)) => Some(parse_input_date_before(&date)),
+                (None, None) => None,
+            };
+
+            let (since, until) = match (since.transpose(), until.transpose()) {
+                (Ok(since), Ok(until)) => (since, until),
+                (Err(err), _) | (_, Err(err)) => return println!("{:?}", err),
+            };
+            let since = match since_last_gc {
+                true => match db::last_gc_at(&conn) {
+                    Ok(run_at) => run_at.map(|run_at| run_at + 1),
+                    Err(err) => return println!("{:?}", err),
+                },
+                false => since,
+            };
+
+            let weeks = match (due_this_week, due_next_week) {
+                (true, _) => Some((0, 7)),
+                (_, true) => Some((7, 14)),
+                _ => None,
+            };
+            let due_between = weeks.map(|(from, to)| -> anyhow::Result<_> {
+                Ok((day_bounds(from)?.0, day_bounds(to)?.0))
+            });
+            let due_between = match due_between.transpose() {
+                Ok(range) => range,
+                Err(err) => return println!("{:?}", err),
+            };
+            let today = match today_due_first.then(|| day_bounds(0)).transpose() {
+                Ok(today) => today,
+                Err(err) => return println!("{:?}", err),
+            };
+
+            let mut columns =
+                due_format.apply(fields.unwrap_or_else(|| db::DEFAULT_COLUMNS.to_vec()));
+            if with_age && !columns.contains(&Column::Age) {
+                let task_at = columns.iter().position(|&column| column == Column::Task);
+                columns.insert(task_at.unwrap_or(columns.len()), Column::Age);
+            }
+
+            let stale_days = stale.map(|days| days.unwrap_or(config.stale_pending_days));
+            let filter = if all {
+                ListFilter::All
+            } else if completed {
+                ListFilter::Completed
+            } else if pending_only || no_in_progress || stale_days.is_some() {
+                ListFilter::Pending
+            } else if in_progress {
+                ListFilter::InProgress
+            } else if blocked {
+                ListFilter::Blocked
+            } else {
+                ListFilter::Active
+            };
+
+            let opts = ListOptions::default()
+                .filter(filter)
+                .reverse(reverse)
+                .overdue_section(!no_overdue_section)
+                .limit(limit)
+                .after_id(from_id)
+                .id_range(with_id_range)
+                .today_due_first(today)
+                .assignee(config.user.filter(|_| mine))
+                .created_after(since)
+                .created_until(until)
+                .priority(priority)
+                .due_null(due_null)
+                .notes_filter(match (has_notes, no_notes) {
+                    (true, _) => Some(NotesFilter::HasNotes),
+                    (_, true) => Some(NotesFilter::NoNotes),
+                    _ => None,
+                })
+                .due_between(due_between)
+                .min_age_days(min_age_days.or(stale_days))
+                .max_age_days(max_age_days)
+                .with_scores(with_scores)
+                .format(if line { DisplayFormat::Line } else { format })
+                .columns(columns)
+                .width(width.or_else(|| output.is_none().then(output::terminal_width).flatten()))
+                .ascii(ascii);
+
+            if export_json_schema {
+                return match serde_json::to_string_pretty(&export::task_json_schema()) {
+                    Ok(schema) => println!("{schema}"),
+                    Err(err) => println!("{:?}", err),
+                };
+            }
+
+            if summary_only {
+                return db::print_summary(&conn, &opts, config.stale_pending_days);
+            }
+
+            // Exit status tells scripts whether anything matched
+            if count_only {
+                match db::query_tasks(&conn, &opts) {
+                    Ok(tasks) => {
+                        println!("{}", tasks.len());
+                        std::process::exit(if tasks.is_empty() { 1 } else { 0 })
+                    }
+                    Err(err) => return println!("{:?}", err),
+                }
+            }
+
+            if interactive {
+                if let Err(err) = tui::run(&conn, opts) {
+                    println!("{:?}", err)
+                }
+                return;
+            }
+
+            match open_output(output) {
+                Ok(mut out) if ndjson => db::list_tasks_ndjson(&conn, &opts, &mut out),
+                Ok(mut out) if json => db::list_tasks_json(&conn, &opts, pretty, &mut out),
+                Ok(mut out) if export_sql => db::list_tasks_sql(&conn, &opts, &mut out),
+                // The rows are already safe, this catches section headers and the like
+                Ok(mut out) if ascii => {
+                    let mut list = vec![];
+                    db::list_tasks(&conn, &opts, &mut list);
+                    let list = output::to_ascii_safe(&String::from_utf8_lossy(&list));
+                    if let Err(err) = out.write_all(list.as_bytes()) {
+                        println!("{:?}", err)
+                    }
+                }
+                Ok(mut out) => db::list_tasks(&conn, &opts, &mut out),
+                Err(err) => println!("{:?}", err),
+            }
+
+            if fail_on_empty && db::query_tasks(&conn, &opts).is_ok_and(|tasks| tasks.is_empty()) {
+                std::process::exit(1)
+            }
+        }
+
+        Commands::Focus => {
+            let opts = ListOptions::default().filter(ListFilter::InProgress);
+            db::list_tasks(&conn, &opts, &mut io::stdout())
+        }
+
+        Commands::Next {
+            id,
+            count,
+            strategy,
+        } => match active {
+            None => match count {
+                Some(n) => db::select_next_batch(&conn, n),
+                None if id.is_some() => db::select_next_task(&conn, id),
+                None => {
+                    let strategy = strategy.unwrap_or(config.next_strategy);
+                    match db::select_next_with_strategy(&conn, strategy, None) {
+                        Ok(Some(id)) => println!("Set task {id} to in progress."),
+                        Ok(None) => println!("No tasks waiting. All done!"),
+                        Err(err) => println!("{:?}", err),
+                    }
+                }
+            },
             Some(_) => {
                 println!(
                     "A task is already active.
@@ -100,36 +1155,335 @@ pub fn run() {
             }
         },
 
-        Commands::Done { id, next } => {
-            db::mark_task_done(&conn, id);
-            if next && active.is_none() {
-                db::select_next_task(&conn, None)
+        Commands::Done {
+            ids,
+            next,
+            note,
+            yes,
+        } => {
+            let ids = ids.concat();
+            if args.dry_run {
+                for id in ids {
+                    println!("[dry-run] Would: mark task [{id}] complete")
+                }
+                if next {
+                    println!("[dry-run] Would: promote the next task to in progress")
+                }
+                return;
+            }
+
+            if ids.len() > 1 && !yes && !confirm(&format!("Complete {} tasks?", ids.len()), "--yes")
+            {
+                return println!("Aborted.");
+            }
+            match note {
+                Some(note) => ids
+                    .iter()
+                    .for_each(|id| db::mark_task_done_with_note(&conn, *id, &note)),
+                None => match ids.as_slice() {
+                    &[id] => db::mark_task_done(&conn, id),
+                    ids => db::mark_tasks_done(&conn, ids),
+                },
+            };
+            if next {
+                db::select_next_task_if_idle(&conn)
             };
         }
 
-        Commands::Show => match active {
-            Some(active) => {
-                db::print_task_header();
-                println!("{active}")
+        Commands::Repeat { id } => match active {
+            None => match db::repeat_task(&conn, id) {
+                Ok(new_id) => println!("Repeated task {id} as {new_id}, set to in progress."),
+                Err(err) => println!("{:?}", err),
+            },
+            Some(_) => {
+                println!(
+                    "A task is already active.
+                    Hint: use `td show` to see current task"
+                )
+            }
+        },
+
+        Commands::Random => match active {
+            None => match db::select_random_task(&conn) {
+                Ok(Some(id)) => println!("Set task {id} to in progress."),
+                Ok(None) => println!("No tasks waiting. All done!"),
+                Err(err) => println!("{:?}", err),
+            },
+            Some(_) => {
+                println!(
+                    "A task is already active.
+                    Hint: use `td show` to see current task"
+                )
             }
-            None => println!(
+        },
+
+        Commands::Show {
+            id,
+            field: Some(field),
+        } => match db::task_to_show(&conn, id) {
+            Ok(Some(task)) => println!("{}", task.field(field)),
+            Ok(None) => std::process::exit(1),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::Show { id, .. } => match db::task_to_show(&conn, id) {
+            Ok(Some(task)) => task.print(),
+            Ok(None) => println!(
                 "No active task.
                 Hint: use `td next` to promote one"
             ),
+            Err(err) => println!("{:?}", err),
         },
 
-        Commands::Pause => match active {
+        Commands::Last { start } => {
+            let task = match db::get_last_added(&conn) {
+                Ok(Some(task)) => task,
+                Ok(None) => return println!("No tasks yet. Hint: use `td add` to add one"),
+                Err(err) => return println!("{:?}", err),
+            };
+            task.print();
+
+            if start {
+                match active {
+                    None => db::select_next_task(&conn, Some(task.id)),
+                    Some(_) => println!(
+                        "A task is already active.
+                        Hint: use `td show` to see current task"
+                    ),
+                }
+            }
+        }
+
+        Commands::Pause { all: true, force } => {
+            if !force && !confirm("Pause all in progress tasks?", "--force") {
+                return println!("Aborted.");
+            }
+            match db::pause_all_active(&conn) {
+                Ok(n) => println!("Paused {n} tasks"),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Pause { .. } => match active {
             Some(active) => db::mark_task_pending(&conn, active),
             None => println!("No active task to pause."),
         },
 
-        Commands::Cancel { id, delete } => {
-            db::mark_task_cancelled(&conn, id);
+        Commands::Cancel {
+            id,
+            delete,
+            reason,
+            yes,
+        } => {
+            if !yes {
+                let task = match db::get_task_by_id(&conn, id) {
+                    Ok(Some(task)) => task,
+                    Ok(None) => return println!("No task with id {id}"),
+                    Err(err) => return println!("{:?}", err),
+                };
+                if !confirm(&format!("Cancel task [{id}] '{}'?", task.task), "--yes") {
+                    return println!("Aborted.");
+                }
+            }
+            match reason {
+                Some(reason) => db::mark_task_cancelled_with_reason(&conn, id, &reason),
+                None => db::mark_task_cancelled(&conn, id),
+            };
             if delete {
                 db::collect_garbage(&conn);
             }
         }
 
-        Commands::Gc => db::collect_garbage(&conn),
+        Commands::Delete { id, yes } => {
+            if !yes {
+                let task = match db::get_task_by_id(&conn, id) {
+                    Ok(Some(task)) => task,
+                    Ok(None) => return println!("No task with id {id}"),
+                    Err(err) => return println!("{:?}", err),
+                };
+                if !confirm(
+                    &format!("Permanently delete task [{id}] '{}'?", task.task),
+                    "--yes",
+                ) {
+                    return println!("Aborted.");
+                }
+            }
+            match db::delete_task(&conn, id) {
+                Ok(_) => println!("Deleted task {id}"),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Block { id, reason } => match reason {
+            Some(reason) => db::mark_task_blocked_with_reason(&conn, id, &reason),
+            None => db::mark_task_blocked(&conn, id),
+        },
+
+        Commands::Watch { interval_ms } => {
+            if let Err(err) = watch::run(&conn, Duration::from_millis(interval_ms)) {
+                println!("{:?}", err)
+            }
+        }
+
+        Commands::Note { id, text } => db::add_task_note(&conn, id, &text),
+
+        Commands::Daily { json, output } => match open_output(output) {
+            Ok(mut out) => db::print_daily_agenda(&conn, json, &mut out),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::Score => db::print_scores(&conn),
+
+        Commands::Progress { json } => db::print_progress(&conn, json),
+
+        Commands::Hist { all } => db::print_priority_histogram(&conn, all),
+
+        Commands::Due { id, date, yes } => {
+            let due_at = match parse_input_date(&date) {
+                Ok(due_at) => due_at,
+                Err(err) => return println!("{:?}", err),
+            };
+            let task = match db::get_task_by_id(&conn, id) {
+                Ok(Some(task)) => task,
+                Ok(None) => return println!("No task with id {id}"),
+                Err(err) => return println!("{:?}", err),
+            };
+
+            let change = format!(
+                "Task [{id}]: due date {} → {}.",
+                db::date_str(task.due_at),
+                db::date_str(Some(due_at))
+            );
+            if yes {
+                println!("{change}")
+            } else if !confirm_default_yes(&format!("{change} Confirm?"), "--yes") {
+                return println!("Aborted.");
+            }
+
+            if let Err(err) = db::update_task_due(&conn, id, due_at) {
+                println!("{:?}", err)
+            }
+        }
+
+        Commands::Assign { id, assignee } => match db::assign_task(&conn, id, &assignee) {
+            Ok(_) => println!("Assigned task {id} to {assignee}"),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::RenameTag { old, new } => match db::rename_tag(&conn, &old, &new) {
+            Ok(n) => println!("Renamed tag \"{old}\" to \"{new}\" on {n} tasks"),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::RemoveTag { tag } => match db::remove_tag(&conn, &tag) {
+            Ok(n) => println!("Removed tag \"{tag}\" from {n} tasks"),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::Export {
+            format,
+            archived,
+            output,
+        } => match open_output(output) {
+            Ok(mut out) => export::export_tasks(&conn, format, archived, &mut out),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::Import { file } => export::import_tasks(&conn, &file),
+
+        Commands::Archive { include_cancelled } => {
+            match db::archive_tasks(&conn, include_cancelled) {
+                Ok(n) => println!("Archived {n} tasks."),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Gc {
+            purge: true, yes, ..
+        } => {
+            if !yes && !confirm("Permanently remove all deleted tasks?", "--yes") {
+                return println!("Aborted.");
+            }
+            match db::purge_deleted(&conn) {
+                Ok(n) => println!("Permanently deleted {n} tasks."),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Gc {
+            restore: Some(id), ..
+        } => match db::restore_task(&conn, id) {
+            Ok(()) => println!("Restored task {id}"),
+            Err(err) => println!("{:?}", err),
+        },
+
+        Commands::Gc {
+            older_than: Some(days),
+            yes,
+            ..
+        } => {
+            if !yes
+                && !confirm(
+                    &format!("Delete tasks finished more than {days} days ago?"),
+                    "--yes",
+                )
+            {
+                return println!("Aborted.");
+            }
+            let cutoff = Utc::now().timestamp() - days * 86400;
+            match db::collect_garbage_older_than(&conn, cutoff) {
+                Ok(n) => {
+                    println!("Deleted {n} tasks. Hint: use `td gc --purge` to remove them for good")
+                }
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Gc { yes, .. } => {
+            if !yes && !confirm("Delete all cancelled tasks?", "--yes") {
+                return println!("Aborted.");
+            }
+            db::collect_garbage(&conn)
+        }
+
+        Commands::PurgeCompleted { older_than, yes } => {
+            let n = match db::count_completed_older_than(&conn, older_than) {
+                Ok(n) => n,
+                Err(err) => return println!("{:?}", err),
+            };
+            println!("{n} completed tasks to remove.");
+
+            if n == 0 {
+                return;
+            }
+            if !yes && !confirm("Permanently remove them?", "--yes") {
+                return println!("Aborted.");
+            }
+            match db::purge_completed_older_than(&conn, older_than) {
+                Ok(n) => println!("Permanently deleted {n} tasks."),
+                Err(err) => println!("{:?}", err),
+            }
+        }
+
+        Commands::Init { reinit, force } => {
+            if reinit {
+                if !force && !confirm("This permanently deletes all tasks. Continue?", "--force") {
+                    return println!("Aborted.");
+                }
+
+                if let Err(err) = db::reinit_db(&conn) {
+                    return println!("{:?}", err);
+                }
+            }
+
+            db::print_db_info(&conn)
+        }
+
+        Commands::Migrate
+        | Commands::Config { .. }
+        | Commands::Benchmark { .. }
+        | Commands::Completions { .. } => {
+            unreachable!()
+        }
     }
 }
@@ -0,0 +1,263 @@
+use crate::db::SelectionStrategy;
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_STALE_AFTER_HOURS: i64 = 24;
+const DEFAULT_PRIORITY: i64 = 3;
+const DEFAULT_STALE_PENDING_DAYS: u32 = 7;
+
+/// Keys of `td config`, named as in the config file.
+pub const KEYS: &[&str] = &[
+    "stale_after_hours",
+    "stale_pending_days",
+    "default_priority",
+    "user",
+    "next_strategy",
+];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Hours a task can stay in progress before a warning is printed
+    pub stale_after_hours: i64,
+    /// Days a task can stay pending before `list --stale` shows it
+    pub stale_pending_days: u32,
+    /// Priority for new tasks when none is given, in [1, 5]
+    pub default_priority: i64,
+    /// Who "me" is when filtering by assignee
+    pub user: Option<String>,
+    /// How `td next` picks a task when no `--strategy` is given
+    pub next_strategy: SelectionStrategy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            stale_after_hours: DEFAULT_STALE_AFTER_HOURS,
+            stale_pending_days: DEFAULT_STALE_PENDING_DAYS,
+            default_priority: DEFAULT_PRIORITY,
+            user: None,
+            next_strategy: SelectionStrategy::default(),
+        }
+    }
+}
+
+/// `~/.config/td/config.toml`, settings in the environment take precedence over it.
+pub fn config_path() -> PathBuf {
+    env::home_dir().unwrap().join(".config/td/config.toml")
+}
+
+impl Config {
+    /// The config file overridden by the `TD_*` environment variables.
+    pub fn load() -> Self {
+        Config::load_from(&config_path())
+    }
+
+    /// Like `load`, with the config file at `path`.
+    pub fn load_from(path: &Path) -> Self {
+        let file = Config::read(path).unwrap_or_else(|err| {
+            eprintln!("Warning: ignoring {}: {err}", path.display());
+            Config::default()
+        });
+
+        file.with_env()
+    }
+
+    /// The settings in the file at `path`, the defaults when there is no file.
+    pub fn read(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(fs::write(path, toml::to_string(self)?)?)
+    }
+
+    pub fn get(&self, key: &str) -> Result<String> {
+        Ok(match key {
+            "stale_after_hours" => self.stale_after_hours.to_string(),
+            "stale_pending_days" => self.stale_pending_days.to_string(),
+            "default_priority" => self.default_priority.to_string(),
+            "user" => self.user.clone().unwrap_or_default(),
+            "next_strategy" => strategy_name(self.next_strategy),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+
+    /// Parses `value` for the setting `key`, an empty `user` unsets it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "stale_after_hours" => self.stale_after_hours = value.parse()?,
+            "stale_pending_days" => self.stale_pending_days = value.parse()?,
+            "default_priority" => match value.parse::<i64>()? {
+                priority @ 1..=5 => self.default_priority = priority,
+                _ => return Err(anyhow!("Priority must be in [1, 5], got {value}")),
+            },
+            "user" => self.user = (!value.is_empty()).then(|| value.to_string()),
+            "next_strategy" => {
+                self.next_strategy = SelectionStrategy::from_str(value, true).map_err(|_| {
+                    let valid: Vec<_> = SelectionStrategy::value_variants()
+                        .iter()
+                        .map(|&strategy| strategy_name(strategy))
+                        .collect();
+                    anyhow!(
+                        "Unknown strategy \"{value}\", expected one of {}",
+                        valid.join(", ")
+                    )
+                })?
+            }
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    fn with_env(self) -> Self {
+        self.with_vars(|key| env::var(key).ok())
+    }
+
+    /// Overrides settings with the `TD_*` variables `get` returns, so tests
+    /// can pass variables without touching the process environment.
+    fn with_vars(self, get: impl Fn(&str) -> Option<String>) -> Self {
+        Config {
+            stale_after_hours: get("TD_STALE_AFTER_HOURS")
+                .and_then(|hours| hours.parse().ok())
+                .unwrap_or(self.stale_after_hours),
+            stale_pending_days: get("TD_STALE_PENDING_DAYS")
+                .and_then(|days| days.parse().ok())
+                .unwrap_or(self.stale_pending_days),
+            default_priority: default_priority_from(&get).unwrap_or(self.default_priority),
+            user: get("TD_USER").or(self.user),
+            next_strategy: next_strategy_from(&get).unwrap_or(self.next_strategy),
+        }
+    }
+}
+
+fn strategy_name(strategy: SelectionStrategy) -> String {
+    strategy
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow!("Unknown key \"{key}\", expected one of {}", KEYS.join(", "))
+}
+
+fn default_priority_from(get: impl Fn(&str) -> Option<String>) -> Option<i64> {
+    let value = get("TD_DEFAULT_PRIORITY")?;
+
+    match value.parse::<i64>() {
+        Ok(priority @ 1..=5) => Some(priority),
+        _ => {
+            eprintln!("Warning: ignoring invalid TD_DEFAULT_PRIORITY \"{value}\", expected [1, 5]");
+            None
+        }
+    }
+}
+
+fn next_strategy_from(get: impl Fn(&str) -> Option<String>) -> Option<SelectionStrategy> {
+    let value = get("TD_NEXT_STRATEGY")?;
+
+    match SelectionStrategy::from_str(&value, true) {
+        Ok(strategy) => Some(strategy),
+        Err(_) => {
+            eprintln!("Warning: ignoring invalid TD_NEXT_STRATEGY \"{value}\"");
+            None
+        }
+    }
+}
+
+#[test]
+fn test_default_priority_from_env() {
+    let with_priority = |value: Option<&str>| {
+        let value = value.map(str::to_string);
+        Config::default()
+            .with_vars(|key| value.clone().filter(|_| key == "TD_DEFAULT_PRIORITY"))
+            .default_priority
+    };
+
+    assert_eq!(with_priority(Some("5")), 5);
+    assert_eq!(with_priority(Some("9")), DEFAULT_PRIORITY);
+    assert_eq!(with_priority(Some("high")), DEFAULT_PRIORITY);
+    assert_eq!(with_priority(None), DEFAULT_PRIORITY);
+}
+
+#[test]
+fn test_next_strategy_from_env() {
+    let with_strategy = |value: Option<&str>| {
+        let value = value.map(str::to_string);
+        Config::default()
+            .with_vars(|key| value.clone().filter(|_| key == "TD_NEXT_STRATEGY"))
+            .next_strategy
+    };
+
+    assert_eq!(with_strategy(Some("oldest")), SelectionStrategy::Oldest);
+    assert_eq!(with_strategy(Some("soonest")), SelectionStrategy::Priority);
+    assert_eq!(with_strategy(None), SelectionStrategy::Priority);
+}
+
+#[test]
+fn test_set_and_get_each_key() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("td/config.toml");
+
+    let values = [
+        ("stale_after_hours", "48"),
+        ("stale_pending_days", "14"),
+        ("default_priority", "5"),
+        ("user", "alice"),
+        ("next_strategy", "oldest"),
+    ];
+    assert_eq!(values.map(|(key, _)| key), KEYS);
+
+    for (key, value) in values {
+        let mut config = Config::read(&path).unwrap();
+        config.set(key, value).unwrap();
+        config.write(&path).unwrap();
+    }
+
+    let config = Config::read(&path).unwrap();
+    for (key, value) in values {
+        assert_eq!(config.get(key).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_set_rejects_invalid_values() {
+    let mut config = Config::default();
+
+    assert!(config.set("default_priority", "9").is_err());
+    assert!(config.set("next_strategy", "soonest").is_err());
+    assert!(config.set("stale_after_hours", "a day").is_err());
+    assert!(config.set("color", "never").is_err());
+    assert!(config.get("color").is_err());
+    assert_eq!(config, Config::default());
+}
+
+#[test]
+fn test_read_missing_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let config = Config::read(&dir.path().join("config.toml")).unwrap();
+    assert_eq!(config, Config::default());
+}
+
+#[test]
+fn test_load_from_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "stale_pending_days = 30\nuser = \"alice\"\n").unwrap();
+
+    let config = Config::load_from(&path);
+    assert_eq!(config.stale_pending_days, 30);
+    assert_eq!(config.default_priority, DEFAULT_PRIORITY);
+}
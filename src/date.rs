@@ -1,5 +1,5 @@
 use anyhow::{Result, anyhow};
-use chrono::{Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime, TimeZone, Utc};
 
 pub fn parse_input_date(s: &str) -> Result<i64> {
     Ok(NaiveDate::parse_from_str(s, "%d.%m.%Y")?
@@ -10,6 +10,70 @@ pub fn parse_input_date(s: &str) -> Result<i64> {
         .timestamp())
 }
 
+/// `2025-09-15`, as written in todo.txt files.
+pub fn parse_iso_date(s: &str) -> Result<i64> {
+    Ok(NaiveDate::parse_from_str(s, "%Y-%m-%d")?
+        .and_time(NaiveTime::default())
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| anyhow!("Failed to convert to local timezone"))?
+        .timestamp())
+}
+
+/// Last second of the given day, for inclusive upper bounds.
+pub fn parse_input_date_end(s: &str) -> Result<i64> {
+    let next_day = NaiveDate::parse_from_str(s, "%d.%m.%Y")?
+        .succ_opt()
+        .ok_or_else(|| anyhow!("Date out of range"))?;
+
+    Ok(next_day
+        .and_time(NaiveTime::default())
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| anyhow!("Failed to convert to local timezone"))?
+        .timestamp()
+        - 1)
+}
+
+/// Last second before the given day, for `created_at < DATE` as an inclusive bound.
+pub fn parse_input_date_before(s: &str) -> Result<i64> {
+    Ok(parse_input_date(s)? - 1)
+}
+
+/// First second after the given day, for `created_at > DATE` as an inclusive bound.
+pub fn parse_input_date_after(s: &str) -> Result<i64> {
+    Ok(parse_input_date_end(s)? + 1)
+}
+
+pub fn start_of_today() -> Result<i64> {
+    Ok(Local::now()
+        .date_naive()
+        .and_time(NaiveTime::default())
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| anyhow!("Failed to convert to local timezone"))?
+        .timestamp())
+}
+
+/// First and last second of the local day `days` days from today.
+pub fn day_bounds(days: u64) -> Result<(i64, i64)> {
+    let day = Local::now()
+        .date_naive()
+        .checked_add_days(Days::new(days))
+        .ok_or_else(|| anyhow!("Date out of range"))?;
+
+    let start_of = |day: NaiveDate| {
+        day.and_time(NaiveTime::default())
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|start| start.timestamp())
+            .ok_or_else(|| anyhow!("Failed to convert to local timezone"))
+    };
+    let next_day = day.succ_opt().ok_or_else(|| anyhow!("Date out of range"))?;
+
+    Ok((start_of(day)?, start_of(next_day)? - 1))
+}
+
 pub fn timestamp_to_local_str(timestamp: i64) -> Result<String> {
     Ok(Utc
         .timestamp_opt(timestamp, 0)
@@ -20,9 +84,124 @@ pub fn timestamp_to_local_str(timestamp: i64) -> Result<String> {
         .to_string())
 }
 
+/// `2025-09-02 Tue`, as in org-mode timestamps.
+pub fn timestamp_to_local_weekday_str(timestamp: i64) -> Result<String> {
+    Ok(Utc
+        .timestamp_opt(timestamp, 0)
+        .earliest()
+        .ok_or_else(|| anyhow!("Invalid timestamp"))?
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %a")
+        .to_string())
+}
+
+pub fn timestamp_to_local_datetime_str(timestamp: i64) -> Result<String> {
+    Ok(Utc
+        .timestamp_opt(timestamp, 0)
+        .earliest()
+        .ok_or_else(|| anyhow!("Invalid timestamp"))?
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string())
+}
+
+/// Whole local days from today to the timestamp, e.g. `in 3d`, `today` or `2d ago`.
+pub fn relative_time(timestamp: i64) -> Result<String> {
+    relative_time_at(timestamp, Utc::now().timestamp())
+}
+
+fn relative_time_at(timestamp: i64, now: i64) -> Result<String> {
+    let local_date = |timestamp: i64| {
+        Utc.timestamp_opt(timestamp, 0)
+            .earliest()
+            .map(|datetime| datetime.with_timezone(&Local).date_naive())
+            .ok_or_else(|| anyhow!("Invalid timestamp"))
+    };
+    let days = (local_date(timestamp)? - local_date(now)?).num_days();
+
+    Ok(match days {
+        0 => "today".to_string(),
+        1.. => format!("in {days}d"),
+        _ => format!("{}d ago", -days),
+    })
+}
+
+/// Serde helpers for storing timestamps as ISO-8601 strings,
+/// use with `#[serde(with = "crate::date::iso8601")]`.
+pub mod iso8601 {
+    use super::*;
+    use serde::{Deserialize, Deserializer, Serializer, de, ser};
+
+    pub fn serialize<S: Serializer>(timestamp: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        let datetime = DateTime::<Utc>::from_timestamp(*timestamp, 0)
+            .ok_or_else(|| ser::Error::custom("Invalid timestamp"))?;
+        serializer.serialize_str(&datetime.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(DateTime::parse_from_rfc3339(&s)
+            .map_err(de::Error::custom)?
+            .timestamp())
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            timestamp: &Option<i64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match timestamp {
+                Some(timestamp) => super::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<i64>, D::Error> {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] i64);
+
+            let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+            Ok(wrapper.map(|Wrapper(timestamp)| timestamp))
+        }
+    }
+}
+
 #[test]
 fn test() {
     let timestamp = parse_input_date("2.9.2025").unwrap();
     let str = timestamp_to_local_str(timestamp).unwrap();
     assert_eq!(&str, "2025-09-02")
 }
+
+#[test]
+fn test_day_bounds() {
+    let (start, end) = day_bounds(0).unwrap();
+    let (tomorrow, _) = day_bounds(1).unwrap();
+
+    assert_eq!(start, start_of_today().unwrap());
+    assert_eq!(end + 1, tomorrow);
+}
+
+#[test]
+fn test_parse_input_date_end() {
+    let start = parse_input_date("2.9.2025").unwrap();
+    let end = parse_input_date_end("2.9.2025").unwrap();
+
+    assert_eq!(timestamp_to_local_str(end).unwrap(), "2025-09-02");
+    assert_eq!(timestamp_to_local_str(end + 1).unwrap(), "2025-09-03");
+    assert!(end > start);
+}
+
+#[test]
+fn test_relative_time() {
+    let now = parse_input_date("10.9.2025").unwrap() + 12 * 3600;
+
+    let relative = |date: &str| relative_time_at(parse_input_date(date).unwrap(), now).unwrap();
+    assert_eq!(relative("10.9.2025"), "today");
+    assert_eq!(relative("13.9.2025"), "in 3d");
+    assert_eq!(relative("8.9.2025"), "2d ago");
+}
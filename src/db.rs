@@ -1,29 +1,58 @@
-use crate::date::{parse_input_date, timestamp_to_local_str};
+use crate::date::{
+    day_bounds, parse_input_date, relative_time, start_of_today, timestamp_to_local_datetime_str,
+    timestamp_to_local_str,
+};
+use crate::output::{paint, to_ascii_safe};
 use anyhow::{Error, anyhow};
 use chrono::Utc;
-use rusqlite::{Connection, Result, Row, Statement, ToSql, params, types::ToSqlOutput};
+use clap::ValueEnum;
+use rusqlite::{
+    Connection, OptionalExtension, Result, Row, Statement, ToSql, params,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::result;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Status {
+/// Ordered as declared, which is also the order of `list --group-by-status`.
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
     Pending,
     InProgress,
     Completed,
     Cancelled,
+    Blocked,
 }
 
-impl From<i64> for Status {
-    fn from(i: i64) -> Self {
+impl TryFrom<i64> for Status {
+    type Error = FromSqlError;
+
+    fn try_from(i: i64) -> result::Result<Self, Self::Error> {
         match i {
-            0 => Status::Pending,
-            1 => Status::InProgress,
-            2 => Status::Completed,
-            3 => Status::Cancelled,
-            _ => panic!(),
+            0 => Ok(Status::Pending),
+            1 => Ok(Status::InProgress),
+            2 => Ok(Status::Completed),
+            3 => Ok(Status::Cancelled),
+            4 => Ok(Status::Blocked),
+            _ => Err(FromSqlError::Other(format!("Invalid status {i}").into())),
         }
     }
 }
 
+/// A corrupt status reads as `Error::FromSqlConversionFailure` instead of panicking
+impl FromSql for Status {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Status::try_from(value.as_i64()?)
+    }
+}
+
 impl From<Status> for i64 {
     fn from(status: Status) -> Self {
         match status {
@@ -31,6 +60,36 @@ impl From<Status> for i64 {
             Status::InProgress => 1,
             Status::Completed => 2,
             Status::Cancelled => 3,
+            Status::Blocked => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Status::Pending => "Pending",
+            Status::InProgress => "In Progress",
+            Status::Completed => "Completed",
+            Status::Cancelled => "Cancelled",
+            Status::Blocked => "Blocked",
+        })
+    }
+}
+
+/// Accepts the displayed names in any case, with or without a space or
+/// underscore in "in progress".
+impl std::str::FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace([' ', '_'], "").as_str() {
+            "pending" => Ok(Status::Pending),
+            "inprogress" => Ok(Status::InProgress),
+            "completed" => Ok(Status::Completed),
+            "cancelled" => Ok(Status::Cancelled),
+            "blocked" => Ok(Status::Blocked),
+            _ => Err(anyhow!("Unknown status \"{s}\"")),
         }
     }
 }
@@ -41,66 +100,488 @@ impl ToSql for Status {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Task {
-    id: i64,
-    task: String,
-    status: Status,
-    priority: i64,
-    created_at: i64,
-    due_at: Option<i64>,
+    pub(crate) id: i64,
+    pub(crate) task: String,
+    pub(crate) status: Status,
+    pub(crate) priority: i64,
+    #[serde(with = "crate::date::iso8601")]
+    pub(crate) created_at: i64,
+    #[serde(default, with = "crate::date::iso8601::option")]
+    pub(crate) due_at: Option<i64>,
+    #[serde(default)]
+    pub(crate) notes: Option<String>,
+    #[serde(default)]
+    pub(crate) tags: Option<String>,
+    #[serde(default, with = "crate::date::iso8601::option")]
+    pub(crate) archived_at: Option<i64>,
+    #[serde(default)]
+    pub(crate) assignee: Option<String>,
+    #[serde(default)]
+    pub(crate) estimate_minutes: Option<i64>,
+    // Not part of the JSON output, see `task_json_schema`
+    #[serde(skip)]
+    pub(crate) completed_at: Option<i64>,
+    #[serde(skip)]
+    pub(crate) depends_on: Option<i64>,
+}
+
+fn priority_symbol(priority: i64) -> &'static str {
+    match priority {
+        1 => ".",
+        2 => "-",
+        3 => "~",
+        4 => "!",
+        _ => "!!!",
+    }
+}
+
+/// The inverse of `priority_symbol`.
+pub fn priority_symbol_to_int(symbol: &str) -> Option<i64> {
+    (1..=5).find(|&priority| priority_symbol(priority) == symbol)
+}
+
+/// A priority given either as a number in [1, 5] or as its symbol, e.g. `!` for 4.
+pub fn parse_priority(s: &str) -> result::Result<i64, String> {
+    match s.parse::<i64>() {
+        Ok(priority @ 1..=5) => Ok(priority),
+        _ => priority_symbol_to_int(s).ok_or_else(|| {
+            format!("Expected a priority in [1, 5] or one of . - ~ ! !!!, got \"{s}\"")
+        }),
+    }
+}
+
+/// A priority given only as its symbol, e.g. `!` for 4.
+pub fn parse_priority_symbol(s: &str) -> result::Result<i64, String> {
+    priority_symbol_to_int(s).ok_or_else(|| format!("Expected one of . - ~ ! !!!, got \"{s}\""))
+}
+
+/// Columns of the table view. `Task` goes last, as it is the only one not padded.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Column {
+    Id,
+    Status,
+    Priority,
+    Created,
+    Due,
+    /// The due date relative to today, see `DueFormat::Relative`
+    #[value(skip)]
+    DueIn,
+    Archived,
+    /// Days since the task was created, see `age_days`
+    Age,
+    Task,
+}
+
+pub const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Id,
+    Column::Status,
+    Column::Priority,
+    Column::Created,
+    Column::Due,
+    Column::Task,
+];
+
+const ARCHIVE_COLUMNS: &[Column] = &[
+    Column::Id,
+    Column::Status,
+    Column::Priority,
+    Column::Created,
+    Column::Archived,
+    Column::Task,
+];
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Status => "STATUS",
+            Column::Priority => "PRIO",
+            Column::Created => "CREATED",
+            Column::Due | Column::DueIn => "DUE",
+            Column::Archived => "ARCHIVED",
+            Column::Age => "AGE",
+            Column::Task => "TASK",
+        }
+    }
+
+    /// Width the column is padded to
+    fn width(self) -> usize {
+        match self {
+            Column::Id => 4,
+            Column::Age => 5,
+            Column::Priority => 6,
+            Column::Status | Column::Created | Column::Due | Column::DueIn | Column::Archived => 11,
+            Column::Task => 0,
+        }
+    }
+
+    /// `text` padded to the column width and followed by a space. AGE is
+    /// right-aligned, so the days line up.
+    fn pad(self, text: &str) -> String {
+        match self {
+            Column::Age => format!("{text:>0$} ", self.width()),
+            _ => format!("{text:<0$} ", self.width()),
+        }
+    }
+}
+
+/// Tasks order the way `td next` picks them, see `NEXT_TASKS_SQL`: the highest
+/// priority first, then the closest due date, then the oldest. Sorting puts the
+/// task to pick first at the front.
+impl Ord for Task {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let key = |task: &Task| {
+            (
+                std::cmp::Reverse(task.priority),
+                task.due_at.is_none(),
+                task.due_at,
+                task.created_at,
+                task.id,
+            )
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl std::fmt::Display for Task {
+    /// A width, as in `{:80}`, is the width of the whole row
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let status_str = match self.status {
-            Status::Pending => "Pending",
-            Status::InProgress => "InProgress",
-            Status::Completed => "Completed",
-            Status::Cancelled => "Cancelled",
-        };
+        write!(f, "{}", self.row(DEFAULT_COLUMNS, f.width()))
+    }
+}
+
+/// When the task should be done, going by its estimate.
+pub fn compute_eta(task: &Task) -> Option<i64> {
+    task.estimate_minutes
+        .map(|minutes| task.created_at + minutes * 60)
+}
+
+/// Shortens `text` to at most `max_chars` characters, marking the cut with `…`.
+pub fn truncate_task_text(text: &str, max_chars: usize) -> Cow<'_, str> {
+    if text.chars().count() <= max_chars {
+        return Cow::Borrowed(text);
+    }
+
+    match max_chars {
+        0 => Cow::Borrowed(""),
+        _ => Cow::Owned(text.chars().take(max_chars - 1).chain(['…']).collect()),
+    }
+}
+
+pub fn date_str(timestamp: Option<i64>) -> String {
+    match timestamp {
+        None => "Never".to_string(),
+        Some(ts) => timestamp_to_local_str(ts).unwrap_or_else(|_| "Invalid Date".to_string()),
+    }
+}
+
+impl Task {
+    pub(crate) fn tag_list(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().flat_map(|tags| tags.split(','))
+    }
+
+    fn created_str(&self) -> String {
+        timestamp_to_local_str(self.created_at).unwrap_or_else(|_| "Invalid Date".to_string())
+    }
+
+    /// Archived tasks show when they were archived in place of the due date
+    fn due_str(&self) -> String {
+        date_str(self.archived_at.or(self.due_at))
+    }
 
-        let priority_str = match self.priority {
-            1 => ".",
-            2 => "-",
-            3 => "~",
-            4 => "!",
-            _ => "!!!",
+    /// The task text in quotes followed by the tags, truncated to `max_chars` if given
+    fn text_cell(&self, max_chars: Option<usize>) -> String {
+        let tags: String = self.tag_list().map(|tag| format!(" #{tag}")).collect();
+
+        // Quotes and tags are kept, only the text is cut
+        let text = match max_chars {
+            Some(max) => {
+                truncate_task_text(&self.task, max.saturating_sub(tags.chars().count() + 2))
+            }
+            None => Cow::Borrowed(self.task.as_str()),
         };
 
-        let created =
-            timestamp_to_local_str(self.created_at).unwrap_or_else(|_| "Invalid Date".to_string());
+        format!("\"{text}\"{tags}")
+    }
+
+    fn cell(&self, column: Column) -> String {
+        match column {
+            Column::Id => self.id.to_string(),
+            Column::Status => self.status.to_string(),
+            Column::Priority => format!("[{:^3}]", priority_symbol(self.priority)),
+            Column::Created => self.created_str(),
+            Column::Due => date_str(self.due_at),
+            Column::DueIn => match self.due_at {
+                None => "Never".to_string(),
+                Some(due) => relative_time(due).unwrap_or_else(|_| "Invalid Date".to_string()),
+            },
+            Column::Archived => date_str(self.archived_at),
+            Column::Age => format!("{}d", age_days(self)),
+            Column::Task => unreachable!("the task text has its own cell"),
+        }
+    }
+
+    /// The bare value of a single column, e.g. for shell prompts.
+    pub fn field(&self, column: Column) -> String {
+        match column {
+            Column::Priority => self.priority.to_string(),
+            Column::Age => age_days(self).to_string(),
+            Column::Task => self.task.clone(),
+            _ => self.cell(column),
+        }
+    }
+
+    /// The table row of the given columns, truncating the task text so the row fits in `width`.
+    pub fn row(&self, columns: &[Column], width: Option<usize>) -> String {
+        self.row_with_width(columns, width).0
+    }
+
+    /// `row` along with its width in characters, not counting color escape codes.
+    fn row_with_width(&self, columns: &[Column], width: Option<usize>) -> (String, usize) {
+        let mut row = String::new();
+        let mut used = 0;
+
+        for &column in columns {
+            if column == Column::Task {
+                let text = self.text_cell(width.map(|width| width.saturating_sub(used)));
+                used += text.chars().count();
+                row += &text;
+                continue;
+            }
+
+            // Pad before painting, escape codes would otherwise count towards the width
+            let cell = column.pad(&self.cell(column));
+            used += cell.chars().count();
+
+            row += &match (column, self.status) {
+                (Column::Status, Status::InProgress) => paint(&cell, 33),
+                (Column::Status, Status::Completed) => paint(&cell, 32),
+                (Column::Status, Status::Cancelled) => paint(&cell, 90),
+                (Column::Status, Status::Blocked) => paint(&cell, 31),
+                _ => cell,
+            };
+        }
+
+        let trimmed = row.trim_end();
+        let used = used - (row.len() - trimmed.len());
+        (trimmed.to_string(), used)
+    }
+
+    /// Whether the task is still open and was due before `cutoff`.
+    fn is_overdue(&self, cutoff: i64) -> bool {
+        matches!(
+            self.status,
+            Status::Pending | Status::InProgress | Status::Blocked
+        ) && self.due_at.is_some_and(|due_at| due_at < cutoff)
+    }
+
+    pub fn print(&self) {
+        print_task_header(DEFAULT_COLUMNS);
+        println!("{self}");
+        self.print_details();
+
+        if is_task_overdue(self) {
+            eprintln!("⚠ This task is overdue!")
+        }
+    }
+
+    pub fn print_details(&self) {
+        let age = age_days(self);
+        let fire = if age > 30 { " 🔥" } else { "" };
+        println!("\nAge: {age} days{fire}");
 
-        let due = match self.due_at {
-            None => "Never".to_string(),
-            Some(ts) => timestamp_to_local_str(ts).unwrap_or_else(|_| "Invalid Date".to_string()),
+        if let Some(assignee) = &self.assignee {
+            println!("\nAssignee: {assignee}")
+        }
+        if let Some(notes) = &self.notes {
+            println!("\nNotes: {notes}")
+        }
+        if let Some(eta) = compute_eta(self) {
+            let eta =
+                timestamp_to_local_datetime_str(eta).unwrap_or_else(|_| "Invalid Date".to_string());
+            println!("\nETA: {eta}")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DisplayFormat {
+    /// Padded columns
+    Table,
+    /// Pipe delimited columns without padding
+    Compact,
+    /// Only the id and the task
+    Minimal,
+    /// One line per task without padding, see `format_compact`
+    Line,
+}
+
+/// How the DUE column shows dates.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DueFormat {
+    /// The date, e.g. 2025-09-02
+    Absolute,
+    /// Days from today, e.g. "in 3d" or "2d ago"
+    Relative,
+}
+
+impl DueFormat {
+    /// `columns` with the DUE column shown in this format.
+    pub fn apply(self, columns: Vec<Column>) -> Vec<Column> {
+        let due = match self {
+            DueFormat::Absolute => Column::Due,
+            DueFormat::Relative => Column::DueIn,
         };
 
-        write!(
-            f,
-            "{:<4} {:<11} [{:^3}]  {:<11} {:<11} \"{}\"",
-            self.id, status_str, priority_str, created, due, self.task
-        )
+        columns
+            .into_iter()
+            .map(|column| match column {
+                Column::Due | Column::DueIn => due,
+                column => column,
+            })
+            .collect()
     }
 }
 
-pub fn print_task_header() {
-    println!(
-        "{:<4} {:<11} {:<6} {:<11} {:<11} {}",
-        "ID", "STATUS", "PRIO", "CREATED", "DUE", "TASK"
+/// `ID status TASK #tags (P:priority DUE:date)`, the due date left out when unset.
+/// Only the priority is colored.
+pub fn format_compact(task: &Task) -> String {
+    let tags: String = task.tag_list().map(|tag| format!(" #{tag}")).collect();
+    let priority = format!("P:{}", task.priority);
+    let priority = match task.priority {
+        5.. => paint(&priority, 31),
+        4 => paint(&priority, 33),
+        _ => priority,
+    };
+    let due = match task.due_at {
+        Some(_) => format!(" DUE:{}", task.due_str()),
+        None => String::new(),
+    };
+
+    format!(
+        "{} {} {}{tags} ({priority}{due})",
+        task.id, task.status, task.task
     )
 }
 
+pub fn format_task(task: &Task, fmt: DisplayFormat) -> String {
+    match fmt {
+        DisplayFormat::Table => task.to_string(),
+        DisplayFormat::Compact => format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            task.id,
+            task.status,
+            priority_symbol(task.priority),
+            task.created_str(),
+            task.due_str(),
+            task.task,
+            task.tags.as_deref().unwrap_or_default()
+        ),
+        DisplayFormat::Minimal => format!("{} {}", task.id, task.task),
+        DisplayFormat::Line => format_compact(task),
+    }
+}
+
+/// Writes the header matching `format_task`, minimal output has none.
+pub fn write_format_header(
+    out: &mut dyn Write,
+    fmt: DisplayFormat,
+    columns: &[Column],
+) -> io::Result<()> {
+    match fmt {
+        DisplayFormat::Table => write_task_header(out, columns),
+        DisplayFormat::Compact => writeln!(out, "ID|STATUS|PRIO|CREATED|DUE|TASK|TAGS"),
+        DisplayFormat::Minimal | DisplayFormat::Line => Ok(()),
+    }
+}
+
+/// Header for rows written with `Task::row` and the same columns.
+pub fn write_task_header(out: &mut dyn Write, columns: &[Column]) -> io::Result<()> {
+    writeln!(out, "{}", task_header(columns))
+}
+
+fn task_header(columns: &[Column]) -> String {
+    let header: String = columns
+        .iter()
+        .map(|column| column.pad(column.header()))
+        .collect();
+
+    header.trim_end().to_string()
+}
+
+/// Width of the SCORE column, right-aligned after the table rows
+const SCORE_WIDTH: usize = 7;
+
+/// Table rows with a SCORE column appended, aligned past the longest row.
+/// Tasks without a score, i.e. ones that are not pending, get an empty cell.
+fn write_tasks_with_scores(
+    out: &mut dyn Write,
+    tasks: &[Task],
+    columns: &[Column],
+    width: Option<usize>,
+    scores: &HashMap<i64, f64>,
+) -> io::Result<()> {
+    let width = width.map(|width| width.saturating_sub(SCORE_WIDTH + 1));
+    let rows: Vec<(String, usize)> = tasks
+        .iter()
+        .map(|task| task.row_with_width(columns, width))
+        .collect();
+
+    let header = task_header(columns);
+    let pad = rows
+        .iter()
+        .map(|(_, used)| *used)
+        .chain([header.chars().count()])
+        .max()
+        .unwrap_or_default();
+
+    writeln!(out, "{header:<pad$} {:>SCORE_WIDTH$}", "SCORE")?;
+    for (task, (row, used)) in tasks.iter().zip(rows) {
+        let score = scores
+            .get(&task.id)
+            .map(|score| format!("{score:.2}"))
+            .unwrap_or_default();
+        let line = format!("{row}{} {score:>SCORE_WIDTH$}", " ".repeat(pad - used));
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+pub fn print_task_header(columns: &[Column]) {
+    write_task_header(&mut io::stdout(), columns).expect("Unable to write to stdout.")
+}
+
 impl TryFrom<&Row<'_>> for Task {
     type Error = rusqlite::Error;
 
     fn try_from(row: &Row) -> Result<Self> {
         Ok(Task {
-            id: row.get(0)?,
-            task: row.get(1)?,
-            status: Status::from(row.get::<_, i64>(2)?),
-            priority: row.get(3)?,
-            created_at: row.get(4)?,
-            due_at: row.get(5)?,
+            id: row.get("id")?,
+            task: row.get("task")?,
+            status: row.get("status")?,
+            priority: row.get("priority")?,
+            created_at: row.get("created_at")?,
+            due_at: row.get("due_at")?,
+            notes: row.get("notes")?,
+            tags: row.get("tags")?,
+            // Only present when reading from `tasks_archive`
+            archived_at: match row.get("archived_at") {
+                Err(rusqlite::Error::InvalidColumnName(_)) => None,
+                archived_at => archived_at?,
+            },
+            assignee: row.get("assignee")?,
+            estimate_minutes: row.get("estimate_minutes")?,
+            completed_at: row.get("completed_at")?,
+            depends_on: row.get("depends_on")?,
         })
     }
 }
@@ -115,250 +596,3940 @@ const TABLE_DDL: &str = "
         due_at INT
     );";
 
-pub fn init_db() -> Connection {
-    let path = std::env::home_dir().unwrap().join(".cache/td");
-    std::fs::create_dir_all(&path).unwrap();
+/// Schema changes applied on top of `TABLE_DDL`, tracked through `PRAGMA user_version`.
+/// Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE tasks ADD COLUMN notes TEXT;",
+    "ALTER TABLE tasks ADD COLUMN depends_on INTEGER REFERENCES tasks(id) ON DELETE SET NULL;",
+    "ALTER TABLE tasks ADD COLUMN tags TEXT;",
+    // Mirrors the columns of `tasks`, see `TASK_COLUMNS`. Ids of deleted tasks
+    // may get reused, so `id` is not unique in the archive.
+    "CREATE TABLE tasks_archive (
+        id INTEGER NOT NULL,
+        task TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        priority INTEGER NOT NULL,
+        created_at INT NOT NULL,
+        due_at INT,
+        notes TEXT,
+        depends_on INTEGER,
+        tags TEXT,
+        archived_at INT NOT NULL
+    );",
+    "ALTER TABLE tasks ADD COLUMN assignee TEXT;
+    ALTER TABLE tasks_archive ADD COLUMN assignee TEXT;",
+    "ALTER TABLE tasks ADD COLUMN completed_at INT;
+    ALTER TABLE tasks_archive ADD COLUMN completed_at INT;",
+    "ALTER TABLE tasks ADD COLUMN estimate_minutes INTEGER;
+    ALTER TABLE tasks_archive ADD COLUMN estimate_minutes INTEGER;",
+    // Set by `gc`, deleted tasks are hidden until restored or purged.
+    // Never archived, so the column is not part of `TASK_COLUMNS`.
+    "ALTER TABLE tasks ADD COLUMN deleted_at INT;",
+    // One row per `gc` run, see `list --since-last-gc`
+    "CREATE TABLE gc_log (run_at INT NOT NULL, deleted_count INT NOT NULL);",
+];
 
-    let conn = Connection::open(path.join("td.db")).expect("Unable to open database.");
-    conn.execute(TABLE_DDL, [])
-        .expect("Unable to create table in database.");
+/// Columns shared by `tasks` and `tasks_archive`, new task columns belong in both tables.
+const TASK_COLUMNS: &str = "id, task, status, priority, created_at, due_at, notes, depends_on, tags, assignee, completed_at, estimate_minutes";
 
-    conn
+fn schema_version(conn: &Connection) -> Result<usize> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
 }
 
-pub fn add_task(conn: &Connection, task: &str, priority: Option<i64>, due: Option<String>) {
-    let due_at = due.and_then(|date| parse_input_date(&date).ok());
-    let created_at = Utc::now().timestamp();
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static str>> {
+    let version = schema_version(conn)?;
+    Ok(MIGRATIONS.iter().skip(version).copied().collect())
+}
 
-    match conn.execute(
-        "INSERT INTO tasks (task, priority, created_at, due_at) VALUES (?1, ?2, ?3, ?4);",
-        params![task, priority.unwrap_or(3), created_at, due_at],
-    ) {
-        Ok(_) => println!("✓ Added task \"{}\"", task),
-        Err(err) => println!("{:?}", err),
+fn migrate(conn: &Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(migration)?;
+        conn.pragma_update(None, "user_version", i + 1)?;
     }
-}
 
-fn select_to_tasks(statement: &mut Statement) -> Result<Vec<Task>> {
-    statement
-        .query_map([], |row| Task::try_from(row))?
-        .collect()
+    Ok(())
 }
 
-pub fn list_tasks(conn: &Connection, all: bool, completed: bool) {
-    let sql = match (all, completed) {
-        (true, _) => "SELECT * FROM tasks;",
-        (false, true) => "SELECT * FROM tasks WHERE status = 2;",
-        (false, false) => {
-            "SELECT * FROM tasks WHERE status IN (0, 1) ORDER BY status DESC, priority DESC;"
-        }
+pub fn run_migrations(conn: &Connection, dry_run: bool) {
+    let (version, pending) = match (schema_version(conn), pending_migrations(conn)) {
+        (Ok(version), Ok(pending)) => (version, pending),
+        (Err(err), _) | (_, Err(err)) => return tracing::error!("{err:?}"),
     };
 
-    let mut statement = conn.prepare(sql).expect("");
+    if pending.is_empty() {
+        return println!("Schema is up to date at version {version}.");
+    }
 
-    match select_to_tasks(&mut statement) {
-        Ok(tasks) => {
-            print_task_header();
-            tasks.iter().for_each(|task| println!("{task}"));
+    if dry_run {
+        for (i, migration) in pending.iter().enumerate() {
+            println!("-- version {}\n{migration}", version + i + 1);
         }
-        Err(_) => println!(""),
+        return;
     }
-}
 
-fn update_task_status(conn: &Connection, id: i64, status: Status) -> result::Result<usize, Error> {
-    match conn.execute(
-        "UPDATE tasks SET status = ?1 WHERE id = ?2",
-        params![status, id],
-    ) {
-        Ok(0) => Err(anyhow!("No rows were updated given id {id}")),
-        Ok(n) => Ok(n),
-        Err(e) => Err(e.into()),
+    match migrate(conn) {
+        Ok(_) => println!(
+            "Migrated schema from version {version} to {}.",
+            version + pending.len()
+        ),
+        Err(err) => tracing::error!("{err:?}"),
     }
 }
 
-pub fn mark_task_done(conn: &Connection, id: i64) {
-    match update_task_status(conn, id, Status::Completed) {
-        Ok(_) => println!("Marked task [{id}] complete"),
-        Err(err) => println!("{:?}", err),
-    }
+pub fn db_path() -> PathBuf {
+    std::env::home_dir().unwrap().join(".cache/td/td.db")
 }
 
-pub fn mark_task_pending(conn: &Connection, task: Task) {
-    match update_task_status(conn, task.id, Status::Pending) {
-        Ok(_) => println!("Paused task {}", task.id),
-        Err(err) => println!("{:?}", err),
-    }
-}
+/// Opens the database without applying pending migrations.
+pub fn open_db() -> Connection {
+    let path = db_path();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
 
-pub fn mark_task_cancelled(conn: &Connection, id: i64) {
-    match update_task_status(conn, id, Status::Cancelled) {
-        Ok(_) => println!("Cancelled task {id}"),
-        Err(err) => println!("{:?}", err),
-    }
+    let conn = Connection::open(path).expect("Unable to open database.");
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .expect("Unable to enable foreign keys.");
+    conn.execute(TABLE_DDL, [])
+        .expect("Unable to create table in database.");
+
+    conn
 }
 
-pub fn select_next_task(conn: &Connection, id: Option<i64>) {
-    let next_id = match id {
-        Some(id) => Ok(id),
-        None => conn.query_row(
-            "SELECT id
-            FROM tasks
-            WHERE status = ?1
-            ORDER BY priority DESC, due_at NULLS LAST, created_at
-            LIMIT 1;",
-            [Status::Pending],
-            |row| row.get(0),
-        ),
-    };
+pub fn init_db() -> Connection {
+    let conn = open_db();
+    migrate(&conn).expect("Unable to migrate database.");
 
-    match next_id {
-        Ok(id) => match update_task_status(conn, id, Status::InProgress) {
-            Ok(_) => println!("Set task {id} to in progress."),
-            Err(_) => println!("No tasks to set to next."),
-        },
-        Err(rusqlite::Error::QueryReturnedNoRows) => println!("No tasks waiting. All done!"),
-        Err(_) => panic!(),
-    }
+    conn
 }
 
-pub fn get_current_active_task(conn: &Connection) -> Option<Task> {
-    match conn.query_row(
-        "SELECT *
-        FROM tasks
-        WHERE status = ?1
-        LIMIT 1;",
-        [Status::InProgress],
-        |row| Task::try_from(row),
-    ) {
-        Ok(task) => Some(task),
-        Err(_) => None,
-    }
+/// A migrated database that only lives as long as the connection.
+pub fn open_in_memory() -> Result<Connection> {
+    let conn = Connection::open_in_memory()?;
+    prepare_db(&conn)?;
+    Ok(conn)
 }
 
-pub fn collect_garbage(conn: &Connection) {
-    match conn.execute("DELETE FROM tasks WHERE status = ?1", [Status::Cancelled]) {
-        Ok(n) => println!("Permanently deleted {n} tasks."),
-        Err(err) => println!("{:?}", err),
-    }
+/// Creates and migrates the schema of a freshly opened connection.
+pub fn prepare_db(conn: &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute(TABLE_DDL, [])?;
+    migrate(conn)
 }
 
-#[cfg(test)]
-fn init_test_db() -> Connection {
-    let conn = Connection::open_in_memory().unwrap();
-    conn.execute(TABLE_DDL, []).unwrap();
-    conn
+/// Drops all tasks and recreates the schema from scratch.
+pub fn reinit_db(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DROP TABLE IF EXISTS tasks_archive;
+        DROP TABLE IF EXISTS gc_log;
+        DROP TABLE IF EXISTS tasks;
+        PRAGMA user_version = 0;",
+    )?;
+    conn.execute(TABLE_DDL, [])?;
+    migrate(conn)
 }
 
-#[cfg(test)]
-fn get_single_task(conn: &Connection) -> Task {
-    conn.query_row("SELECT * FROM tasks", [], |row| Task::try_from(row))
-        .unwrap()
+fn write_db_info(conn: &Connection, path: &Path, out: &mut dyn Write) -> result::Result<(), Error> {
+    writeln!(out, "Database: {}", path.display())?;
+    writeln!(out, "SQLite version: {}", rusqlite::version())?;
+    writeln!(out, "Schema version: {}", schema_version(conn)?)?;
+    Ok(())
 }
 
-#[cfg(test)]
-fn count_tasks(conn: &Connection) -> usize {
-    let mut statement = conn.prepare("SELECT COUNT(*) FROM tasks").unwrap();
-    statement.query_row([], |row| row.get(0)).unwrap()
+pub fn print_db_info(conn: &Connection) {
+    if let Err(err) = write_db_info(conn, &db_path(), &mut io::stdout()) {
+        tracing::error!("{err:?}")
+    }
 }
 
-#[cfg(test)]
-macro_rules! add_task {
-    ($conn:expr, $task:expr) => {
-        add_task($conn, $task, None, None)
-    };
-    ($conn:expr, $task:expr, $priority:expr) => {
-        add_task($conn, $task, Some($priority), None)
-    };
-    ($conn:expr, $task:expr, $priority:expr, $due:expr) => {
-        add_task($conn, $task, Some($priority), Some($due))
-    };
+/// Tags are stored as a single comma separated string, e.g. "work,home".
+fn normalize_tags(tags: &str) -> Option<String> {
+    let tags: Vec<&str> = tags
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    (!tags.is_empty()).then(|| tags.join(","))
 }
 
-#[test]
-fn test_add_tasks() {
-    let conn = init_test_db();
+pub fn add_task(
+    conn: &Connection,
+    task: &str,
+    priority: Option<i64>,
+    due: Option<String>,
+    tags: Option<String>,
+    assignee: Option<String>,
+    estimate_minutes: Option<i64>,
+) -> Result<i64> {
+    let due_at = due.and_then(|date| parse_input_date(&date).ok());
+    let created_at = Utc::now().timestamp();
+    let tags = tags.and_then(|tags| normalize_tags(&tags));
 
-    add_task!(&conn, "Test task");
+    conn.query_row(
+        "INSERT INTO tasks (task, priority, created_at, due_at, tags, assignee, estimate_minutes)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        RETURNING id;",
+        params![
+            task,
+            priority.unwrap_or(3),
+            created_at,
+            due_at,
+            tags,
+            assignee,
+            estimate_minutes
+        ],
+        |row| row.get(0),
+    )
+}
 
-    assert_eq!(count_tasks(&conn), 1);
+/// Inserts a task read from elsewhere under a new id, keeping its status and timestamps.
+pub fn insert_task(conn: &Connection, task: &Task) -> Result<i64> {
+    conn.query_row(
+        "INSERT INTO tasks (task, status, priority, created_at, due_at, notes, tags, assignee, completed_at, estimate_minutes)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        RETURNING id;",
+        params![
+            task.task,
+            task.status,
+            task.priority,
+            task.created_at,
+            task.due_at,
+            task.notes,
+            task.tags,
+            task.assignee,
+            task.completed_at,
+            task.estimate_minutes
+        ],
+        |row| row.get(0),
+    )
+}
 
-    add_task!(&conn, "Test task");
-    add_task!(&conn, "Test task");
-    add_task!(&conn, "Test task");
+fn select_to_tasks(statement: &mut Statement) -> Result<Vec<Task>> {
+    statement
+        .query_map([], |row| Task::try_from(row))?
+        .collect()
+}
 
-    assert_eq!(count_tasks(&conn), 4);
+#[derive(Debug, Clone, Copy)]
+enum SortKey {
+    Asc(&'static str),
+    Desc(&'static str),
 }
 
-#[test]
-fn test_mark_done() {
-    let conn = init_test_db();
+impl SortKey {
+    fn reversed(self) -> Self {
+        match self {
+            SortKey::Asc(column) => SortKey::Desc(column),
+            SortKey::Desc(column) => SortKey::Asc(column),
+        }
+    }
+}
 
-    add_task!(&conn, "Test task");
-    mark_task_done(&conn, 1);
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SortKey::Asc(column) => write!(f, "{column} ASC"),
+            SortKey::Desc(column) => write!(f, "{column} DESC"),
+        }
+    }
+}
 
-    let task = get_single_task(&conn);
+fn order_by_clause(keys: &[SortKey], reverse: bool) -> String {
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|key| if reverse { key.reversed() } else { *key })
+        .map(|key| key.to_string())
+        .collect();
+
+    format!("ORDER BY {}", keys.join(", "))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ListFilter {
+    /// Pending, in progress and blocked tasks
+    Active,
+    All,
+    Completed,
+    Pending,
+    InProgress,
+    Blocked,
+}
+
+/// Whether tasks should have notes, empty notes count as none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotesFilter {
+    HasNotes,
+    NoNotes,
+}
+
+/// Options for `list_tasks`, built up from `ListOptions::default()`.
+#[derive(Debug, Clone)]
+pub struct ListOptions {
+    filter: ListFilter,
+    reverse: bool,
+    overdue_section: bool,
+    limit: Option<usize>,
+    assignee: Option<String>,
+    created_after: Option<i64>,
+    created_until: Option<i64>,
+    due_null: bool,
+    notes_filter: Option<NotesFilter>,
+    priority: Option<i64>,
+    due_between: Option<(i64, i64)>,
+    min_age_days: Option<u32>,
+    max_age_days: Option<u32>,
+    // `created_at` bounds for the ages, fixed when the age is set
+    min_age_cutoff: Option<i64>,
+    max_age_cutoff: Option<i64>,
+    with_scores: bool,
+    format: DisplayFormat,
+    width: Option<usize>,
+    ascii: bool,
+    after_id: Option<i64>,
+    id_range: Option<(i64, i64)>,
+    today_due_first: Option<(i64, i64)>,
+    columns: Vec<Column>,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            filter: ListFilter::Active,
+            reverse: false,
+            overdue_section: true,
+            limit: None,
+            assignee: None,
+            created_after: None,
+            created_until: None,
+            due_null: false,
+            notes_filter: None,
+            priority: None,
+            due_between: None,
+            min_age_days: None,
+            max_age_days: None,
+            min_age_cutoff: None,
+            max_age_cutoff: None,
+            with_scores: false,
+            format: DisplayFormat::Table,
+            width: None,
+            ascii: false,
+            after_id: None,
+            id_range: None,
+            today_due_first: None,
+            columns: DEFAULT_COLUMNS.to_vec(),
+        }
+    }
+}
+
+impl ListOptions {
+    pub fn filter(mut self, filter: ListFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    pub fn overdue_section(mut self, overdue_section: bool) -> Self {
+        self.overdue_section = overdue_section;
+        self
+    }
+
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn assignee(mut self, assignee: Option<String>) -> Self {
+        self.assignee = assignee;
+        self
+    }
+
+    pub fn format(mut self, format: DisplayFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Only tasks with an id above this one. Together with `limit` this pages
+    /// through tasks without SQLite scanning the skipped rows.
+    pub fn after_id(mut self, id: Option<i64>) -> Self {
+        self.after_id = id;
+        self
+    }
+
+    /// Show tasks due within `today`, a range of timestamps, first, then the
+    /// rest in selection order. Replaces the filter's own order.
+    pub fn today_due_first(mut self, today: Option<(i64, i64)>) -> Self {
+        self.today_due_first = today;
+        self
+    }
+
+    /// Only tasks with an id in the inclusive range
+    pub fn id_range(mut self, range: Option<(i64, i64)>) -> Self {
+        self.id_range = range;
+        self
+    }
+
+    /// Columns of the table view, `Task` should go last
+    pub fn columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Truncate table rows to this many characters
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Replace non-ASCII characters in the task text and tags, see `to_ascii_safe`
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Only tasks created at or after the timestamp
+    pub fn created_after(mut self, timestamp: Option<i64>) -> Self {
+        self.created_after = timestamp;
+        self
+    }
+
+    /// Only tasks created at or before the timestamp
+    pub fn created_until(mut self, timestamp: Option<i64>) -> Self {
+        self.created_until = timestamp;
+        self
+    }
+
+    /// Only tasks without a due date
+    pub fn due_null(mut self, due_null: bool) -> Self {
+        self.due_null = due_null;
+        self
+    }
+
+    /// Only tasks with, or only tasks without notes
+    pub fn notes_filter(mut self, notes_filter: Option<NotesFilter>) -> Self {
+        self.notes_filter = notes_filter;
+        self
+    }
+
+    /// Only tasks of this priority
+    pub fn priority(mut self, priority: Option<i64>) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Only tasks due at or after the first and before the second timestamp
+    pub fn due_between(mut self, range: Option<(i64, i64)>) -> Self {
+        self.due_between = range;
+        self
+    }
+
+    /// Only tasks created at least this many days ago
+    pub fn min_age_days(mut self, days: Option<u32>) -> Self {
+        self.min_age_days = days;
+        self.min_age_cutoff = days.map(age_cutoff);
+        self
+    }
+
+    /// Only tasks created at most this many days ago
+    pub fn max_age_days(mut self, days: Option<u32>) -> Self {
+        self.max_age_days = days;
+        self.max_age_cutoff = days.map(age_cutoff);
+        self
+    }
+
+    /// Append the score of `compute_scores` to table rows
+    pub fn with_scores(mut self, with_scores: bool) -> Self {
+        self.with_scores = with_scores;
+        self
+    }
+}
+
+/// Creation time of a task that is `days` old now.
+fn age_cutoff(days: u32) -> i64 {
+    Utc::now().timestamp() - i64::from(days) * 86400
+}
+
+/// The `WHERE` conditions of `opts` other than the status filter, with their bound values.
+fn filter_conditions(opts: &ListOptions) -> (Vec<String>, Vec<&dyn ToSql>) {
+    let mut conditions = vec!["deleted_at IS NULL".to_string()];
+    let mut values: Vec<&dyn ToSql> = vec![];
+
+    if let Some(assignee) = &opts.assignee {
+        conditions.push("assignee = ?".to_string());
+        values.push(assignee);
+    }
+
+    if let Some(timestamp) = &opts.created_after {
+        conditions.push("created_at >= ?".to_string());
+        values.push(timestamp);
+    }
+
+    if let Some(timestamp) = &opts.created_until {
+        conditions.push("created_at <= ?".to_string());
+        values.push(timestamp);
+    }
+
+    if opts.due_null {
+        conditions.push("due_at IS NULL".to_string());
+    }
+
+    match opts.notes_filter {
+        Some(NotesFilter::HasNotes) => {
+            conditions.push("notes IS NOT NULL AND notes != ''".to_string())
+        }
+        Some(NotesFilter::NoNotes) => conditions.push("(notes IS NULL OR notes = '')".to_string()),
+        None => {}
+    }
+
+    if let Some(priority) = &opts.priority {
+        conditions.push("priority = ?".to_string());
+        values.push(priority);
+    }
+
+    if let Some((from, to)) = &opts.due_between {
+        conditions.push("due_at >= ? AND due_at < ?".to_string());
+        values.push(from);
+        values.push(to);
+    }
+
+    if let Some(cutoff) = &opts.min_age_cutoff {
+        conditions.push("created_at <= ?".to_string());
+        values.push(cutoff);
+    }
+
+    if let Some(cutoff) = &opts.max_age_cutoff {
+        conditions.push("created_at >= ?".to_string());
+        values.push(cutoff);
+    }
+
+    if let Some(id) = &opts.after_id {
+        conditions.push("id > ?".to_string());
+        values.push(id);
+    }
+
+    if let Some((from, to)) = &opts.id_range {
+        conditions.push("id BETWEEN ? AND ?".to_string());
+        values.push(from);
+        values.push(to);
+    }
+
+    (conditions, values)
+}
+
+pub fn query_tasks(conn: &Connection, opts: &ListOptions) -> Result<Vec<Task>> {
+    let (status, keys): (&str, &[SortKey]) = match opts.filter {
+        ListFilter::All => ("TRUE", &[SortKey::Asc("id")]),
+        ListFilter::Completed => ("status = 2", &[SortKey::Asc("id")]),
+        ListFilter::Pending => ("status = 0", &[SortKey::Desc("priority")]),
+        ListFilter::InProgress => ("status = 1", &[SortKey::Desc("priority")]),
+        ListFilter::Blocked => ("status = 4", &[SortKey::Desc("priority")]),
+        ListFilter::Active => (
+            "status IN (0, 1, 4)",
+            &[
+                SortKey::Asc("status = 4"),
+                SortKey::Desc("status"),
+                SortKey::Desc("priority"),
+            ],
+        ),
+    };
+
+    let (mut conditions, mut values) = filter_conditions(opts);
+    conditions.insert(0, status.to_string());
+
+    // Sorted before the limit, so `--limit` keeps the tasks due today
+    let keys = match &opts.today_due_first {
+        Some((start, end)) => {
+            values.push(start);
+            values.push(end);
+            &[
+                SortKey::Desc("due_at BETWEEN ? AND ?"),
+                SortKey::Desc("priority"),
+                SortKey::Asc("due_at IS NULL"),
+                SortKey::Asc("due_at"),
+                SortKey::Asc("created_at"),
+                SortKey::Asc("id"),
+            ]
+        }
+        None => keys,
+    };
+
+    let limit = match opts.limit {
+        Some(limit) => format!("LIMIT {limit}"),
+        None => String::new(),
+    };
+
+    let sql = format!(
+        "SELECT * FROM tasks WHERE {} {} {limit};",
+        conditions.join(" AND "),
+        order_by_clause(keys, opts.reverse)
+    );
+
+    conn.prepare(&sql)?
+        .query_map(values.as_slice(), |row| Task::try_from(row))?
+        .collect()
+}
+
+/// Number of tasks per status, statuses without tasks are left out.
+pub fn count_by_status(conn: &Connection) -> Result<HashMap<Status, usize>> {
+    count_by_status_matching(conn, &ListOptions::default())
+}
+
+/// Like `count_by_status`, only counting tasks matching the filters of `opts`.
+/// The status filter of `opts` is ignored.
+pub fn count_by_status_matching(
+    conn: &Connection,
+    opts: &ListOptions,
+) -> Result<HashMap<Status, usize>> {
+    let (conditions, values) = filter_conditions(opts);
+    let sql = format!(
+        "SELECT status, COUNT(*) FROM tasks WHERE {} GROUP BY status",
+        conditions.join(" AND ")
+    );
+
+    conn.prepare(&sql)?
+        .query_map(values.as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect()
+}
+
+/// Number of open tasks matching the filters of `opts` that were due before `cutoff`.
+pub fn count_overdue(conn: &Connection, opts: &ListOptions, cutoff: i64) -> Result<usize> {
+    let (mut conditions, mut values) = filter_conditions(opts);
+    conditions.push("status IN (0, 1, 4) AND due_at < ?".to_string());
+    values.push(&cutoff);
+
+    conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM tasks WHERE {}",
+            conditions.join(" AND ")
+        ),
+        values.as_slice(),
+        |row| row.get(0),
+    )
+}
+
+/// `Pending: 5 | In Progress: 1 | Overdue: 2 | Stale: 1` for the tasks matching `opts`,
+/// pending tasks count as stale once `stale_days` old.
+pub fn print_summary(conn: &Connection, opts: &ListOptions, stale_days: u32) {
+    let today = start_of_today().unwrap_or_else(|_| Utc::now().timestamp());
+    let stale = opts
+        .clone()
+        .min_age_days(opts.min_age_days.max(Some(stale_days)));
+
+    match (
+        count_by_status_matching(conn, opts),
+        count_overdue(conn, opts, today),
+        count_by_status_matching(conn, &stale),
+    ) {
+        (Ok(counts), Ok(overdue), Ok(stale)) => println!(
+            "Pending: {} | In Progress: {} | Overdue: {overdue} | Stale: {}",
+            counts.get(&Status::Pending).unwrap_or(&0),
+            counts.get(&Status::InProgress).unwrap_or(&0),
+            stale.get(&Status::Pending).unwrap_or(&0),
+        ),
+        (Err(err), ..) | (_, Err(err), _) | (.., Err(err)) => tracing::error!("{err:?}"),
+    }
+}
+
+/// Pending tasks created at least `threshold_days` ago, i.e. never started.
+pub fn list_stale_tasks(conn: &Connection, threshold_days: u32) -> Result<Vec<Task>> {
+    query_tasks(
+        conn,
+        &ListOptions::default()
+            .filter(ListFilter::Pending)
+            .min_age_days(Some(threshold_days)),
+    )
+}
+
+pub fn is_task_overdue(task: &Task) -> bool {
+    task.is_overdue(Utc::now().timestamp())
+}
+
+/// Splits tasks into (overdue, the rest), preserving order within both groups.
+pub fn partition_by_overdue(tasks: Vec<Task>) -> (Vec<Task>, Vec<Task>) {
+    let today = start_of_today().unwrap_or_else(|_| Utc::now().timestamp());
+    tasks.into_iter().partition(|task| task.is_overdue(today))
+}
+
+#[derive(Serialize)]
+pub struct DailyAgenda {
+    pub overdue: Vec<Task>,
+    pub due_today: Vec<Task>,
+    pub in_progress: Vec<Task>,
+}
+
+pub fn daily_agenda(conn: &Connection) -> Result<DailyAgenda> {
+    let today = start_of_today().unwrap_or_else(|_| Utc::now().timestamp());
+    let tomorrow = today + 24 * 3600;
+
+    let mut agenda = DailyAgenda {
+        overdue: vec![],
+        due_today: vec![],
+        in_progress: vec![],
+    };
+
+    for task in query_tasks(conn, &ListOptions::default())? {
+        if task.status == Status::InProgress {
+            agenda.in_progress.push(task);
+        } else if task.is_overdue(today) {
+            agenda.overdue.push(task);
+        } else if task.due_at.is_some_and(|due_at| due_at < tomorrow) {
+            agenda.due_today.push(task);
+        }
+    }
+
+    Ok(agenda)
+}
+
+/// Table rows are truncated to `width` characters, when given.
+fn write_tasks(
+    out: &mut dyn Write,
+    tasks: &[Task],
+    fmt: DisplayFormat,
+    columns: &[Column],
+    width: Option<usize>,
+) -> io::Result<()> {
+    write_format_header(out, fmt, columns)?;
+    tasks.iter().try_for_each(|task| match fmt {
+        DisplayFormat::Table => writeln!(out, "{}", task.row(columns, width)),
+        _ => writeln!(out, "{}", format_task(task, fmt)),
+    })
+}
+
+fn write_section(out: &mut dyn Write, title: &str, tasks: &[Task]) -> io::Result<()> {
+    writeln!(out, "{title} ({})", tasks.len())?;
+    if !tasks.is_empty() {
+        write_tasks(out, tasks, DisplayFormat::Table, DEFAULT_COLUMNS, None)?;
+    }
+    Ok(())
+}
+
+fn write_daily_agenda(
+    conn: &Connection,
+    json: bool,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    let agenda = daily_agenda(conn)?;
+
+    if json {
+        serde_json::to_writer(&mut *out, &agenda)?;
+        return Ok(writeln!(out)?);
+    }
+
+    write_section(out, "⚠ OVERDUE", &agenda.overdue)?;
+    writeln!(out)?;
+    write_section(out, "DUE TODAY", &agenda.due_today)?;
+    writeln!(out)?;
+    Ok(write_section(out, "IN PROGRESS", &agenda.in_progress)?)
+}
+
+pub fn print_daily_agenda(conn: &Connection, json: bool, out: &mut dyn Write) {
+    if let Err(err) = write_daily_agenda(conn, json, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+/// Groups active tasks by tag, tasks with several tags show up in each group.
+/// Untagged tasks are collected last under "[untagged]".
+pub fn tasks_grouped_by_tag(conn: &Connection) -> Result<Vec<(String, Vec<Task>)>> {
+    let mut groups: BTreeMap<String, Vec<Task>> = BTreeMap::new();
+    let mut untagged = vec![];
+
+    for task in query_tasks(conn, &ListOptions::default())? {
+        match &task.tags {
+            Some(tags) => tags.split(',').for_each(|tag| {
+                groups
+                    .entry(tag.to_string())
+                    .or_default()
+                    .push(task.clone())
+            }),
+            None => untagged.push(task),
+        }
+    }
+
+    let mut groups: Vec<(String, Vec<Task>)> = groups.into_iter().collect();
+    if !untagged.is_empty() {
+        groups.push(("[untagged]".to_string(), untagged));
+    }
+
+    Ok(groups)
+}
+
+fn write_tasks_grouped_by_tag(conn: &Connection, out: &mut dyn Write) -> result::Result<(), Error> {
+    for (i, (tag, tasks)) in tasks_grouped_by_tag(conn)?.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        write_section(out, tag, tasks)?;
+    }
+
+    Ok(())
+}
+
+pub fn tasks_by_status(conn: &Connection) -> Result<BTreeMap<Status, Vec<Task>>> {
+    let mut groups: BTreeMap<Status, Vec<Task>> = BTreeMap::new();
+
+    for task in query_tasks(conn, &ListOptions::default().filter(ListFilter::All))? {
+        groups.entry(task.status).or_default().push(task);
+    }
+
+    Ok(groups)
+}
+
+fn status_heading(status: Status) -> &'static str {
+    match status {
+        Status::Pending => "## Pending",
+        Status::InProgress => "## In Progress",
+        Status::Completed => "## Completed",
+        Status::Cancelled => "## Cancelled",
+        Status::Blocked => "## Blocked",
+    }
+}
+
+fn write_tasks_by_status(
+    conn: &Connection,
+    show_empty: bool,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    let mut groups = tasks_by_status(conn)?;
+
+    if show_empty {
+        for status in (0..=4).filter_map(|i| Status::try_from(i).ok()) {
+            groups.entry(status).or_default();
+        }
+    }
+
+    for (i, (status, tasks)) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        write_section(out, status_heading(*status), tasks)?;
+    }
+
+    Ok(())
+}
+
+pub fn list_tasks_by_status(conn: &Connection, show_empty: bool, out: &mut dyn Write) {
+    if let Err(err) = write_tasks_by_status(conn, show_empty, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+pub fn list_tasks_grouped_by_tag(conn: &Connection, out: &mut dyn Write) {
+    if let Err(err) = write_tasks_grouped_by_tag(conn, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+fn write_task_list(
+    conn: &Connection,
+    opts: &ListOptions,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    let mut tasks = query_tasks(conn, opts)?;
+    // Replaced before rows are cut to the width, `[x]` is wider than `✓`
+    if opts.ascii {
+        for task in &mut tasks {
+            task.task = to_ascii_safe(&task.task);
+            task.tags = task.tags.as_deref().map(to_ascii_safe);
+        }
+    }
+
+    let scores: Option<HashMap<i64, f64>> = match opts.with_scores {
+        true => Some(
+            compute_scores(conn)?
+                .into_iter()
+                .map(|(task, score)| (task.id, score))
+                .collect(),
+        ),
+        false => None,
+    };
+    let write = |out: &mut dyn Write, tasks: &[Task]| match &scores {
+        Some(scores) => write_tasks_with_scores(out, tasks, &opts.columns, opts.width, scores),
+        None => write_tasks(out, tasks, opts.format, &opts.columns, opts.width),
+    };
+
+    let (overdue, tasks) = match opts.overdue_section {
+        true => partition_by_overdue(tasks),
+        false => (vec![], tasks),
+    };
+
+    if !overdue.is_empty() {
+        writeln!(out, "⚠ OVERDUE")?;
+        write(out, &overdue)?;
+        writeln!(out)?;
+    }
+
+    Ok(write(out, &tasks)?)
+}
+
+/// Writes one JSON object per line, flushing as it goes so consumers can stream.
+fn write_tasks_ndjson(
+    conn: &Connection,
+    opts: &ListOptions,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    for task in query_tasks(conn, opts)? {
+        serde_json::to_writer(&mut *out, &task)?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+pub fn list_tasks_ndjson(conn: &Connection, opts: &ListOptions, out: &mut dyn Write) {
+    if let Err(err) = write_tasks_ndjson(conn, opts, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+fn write_tasks_json(
+    conn: &Connection,
+    opts: &ListOptions,
+    pretty: bool,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    let tasks = query_tasks(conn, opts)?;
+
+    match pretty {
+        true => serde_json::to_writer_pretty(&mut *out, &tasks)?,
+        false => serde_json::to_writer(&mut *out, &tasks)?,
+    }
+
+    Ok(writeln!(out)?)
+}
+
+pub fn list_tasks_json(conn: &Connection, opts: &ListOptions, pretty: bool, out: &mut dyn Write) {
+    if let Err(err) = write_tasks_json(conn, opts, pretty, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+/// SQL literal for an optional value, strings are quoted with `'` doubled inside.
+fn sql_literal<T: ToString>(value: Option<T>, quote: bool) -> String {
+    match value {
+        None => "NULL".to_string(),
+        Some(value) if quote => format!("'{}'", value.to_string().replace('\'', "''")),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// An `INSERT` statement recreating the task, for debugging and manual migrations.
+pub fn task_to_insert_sql(task: &Task) -> String {
+    let values: Vec<String> = TASK_COLUMNS
+        .split(", ")
+        .map(|column| match column {
+            "id" => task.id.to_string(),
+            "task" => sql_literal(Some(&task.task), true),
+            "status" => i64::from(task.status).to_string(),
+            "priority" => task.priority.to_string(),
+            "created_at" => task.created_at.to_string(),
+            "due_at" => sql_literal(task.due_at, false),
+            "notes" => sql_literal(task.notes.as_ref(), true),
+            "depends_on" => sql_literal(task.depends_on, false),
+            "tags" => sql_literal(task.tags.as_ref(), true),
+            "assignee" => sql_literal(task.assignee.as_ref(), true),
+            "completed_at" => sql_literal(task.completed_at, false),
+            "estimate_minutes" => sql_literal(task.estimate_minutes, false),
+            _ => unreachable!("Task has no field for column {column}"),
+        })
+        .collect();
+
+    format!(
+        "INSERT INTO tasks ({TASK_COLUMNS}) VALUES ({});",
+        values.join(", ")
+    )
+}
+
+fn write_tasks_sql(
+    conn: &Connection,
+    opts: &ListOptions,
+    out: &mut dyn Write,
+) -> result::Result<(), Error> {
+    for task in query_tasks(conn, opts)? {
+        writeln!(out, "{}", task_to_insert_sql(&task))?;
+    }
+    Ok(())
+}
+
+pub fn list_tasks_sql(conn: &Connection, opts: &ListOptions, out: &mut dyn Write) {
+    if let Err(err) = write_tasks_sql(conn, opts, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+pub fn list_tasks(conn: &Connection, opts: &ListOptions, out: &mut dyn Write) {
+    if let Err(err) = write_task_list(conn, opts, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+pub fn update_task_status(
+    conn: &Connection,
+    id: i64,
+    status: Status,
+) -> result::Result<usize, Error> {
+    // Completing or cancelling twice would otherwise report success
+    if matches!(status, Status::Completed | Status::Cancelled) {
+        let current = get_task_by_id(conn, id)?.map(|task| task.status);
+
+        if current == Some(status) {
+            let status = format!("{status:?}").to_lowercase();
+            return Err(anyhow!("Task {id} is already {status}"));
+        }
+    }
+
+    // `completed_at` tracks when the task was completed or cancelled
+    match conn.execute(
+        "UPDATE tasks
+        SET status = ?1, completed_at = CASE WHEN ?1 IN (2, 3) THEN ?3 END
+        WHERE id = ?2 AND deleted_at IS NULL",
+        params![status, id, Utc::now().timestamp()],
+    ) {
+        Ok(0) => Err(anyhow!("No rows were updated given id {id}")),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Completes each task on its own, so one bad id doesn't hold back the rest.
+/// Returns the completed ids and the ids that failed along with why.
+pub fn bulk_mark_done(conn: &Connection, ids: &[i64]) -> (Vec<i64>, Vec<(i64, Error)>) {
+    let mut done = vec![];
+    let mut failed = vec![];
+
+    for &id in ids {
+        match update_task_status(conn, id, Status::Completed) {
+            Ok(_) => done.push(id),
+            Err(err) => failed.push((id, err)),
+        }
+    }
+
+    (done, failed)
+}
+
+pub fn mark_task_done(conn: &Connection, id: i64) {
+    mark_tasks_done(conn, &[id])
+}
+
+/// Completes the tasks in one transaction, skipping the ones that can't be completed.
+pub fn mark_tasks_done(conn: &Connection, ids: &[i64]) {
+    let tx = match conn.unchecked_transaction() {
+        Ok(tx) => tx,
+        Err(err) => return tracing::error!("{err:?}"),
+    };
+    let (done, failed) = bulk_mark_done(&tx, ids);
+
+    if let Err(err) = tx.commit() {
+        return tracing::error!("{err:?}");
+    }
+
+    for id in done {
+        println!("Marked task [{id}] complete")
+    }
+    for (_, err) in &failed {
+        tracing::error!("{err:?}")
+    }
+    if ids.len() > 1 && !failed.is_empty() {
+        let skipped: Vec<String> = failed.iter().map(|(id, _)| id.to_string()).collect();
+        println!("Skipped tasks {}", skipped.join(", "))
+    }
+}
+
+/// Appends a line to the task notes, keeping whatever was recorded before.
+pub fn append_task_notes(conn: &Connection, id: i64, notes: &str) -> result::Result<usize, Error> {
+    match conn.execute(
+        "UPDATE tasks SET notes = COALESCE(notes || char(10), '') || ?1
+        WHERE id = ?2 AND deleted_at IS NULL",
+        params![notes, id],
+    ) {
+        Ok(0) => Err(anyhow!("No task with id {id}")),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn rename_task(conn: &Connection, id: i64, task: &str) -> result::Result<usize, Error> {
+    match conn.execute(
+        "UPDATE tasks SET task = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![task, id],
+    ) {
+        Ok(0) => Err(anyhow!("No task with id {id}")),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn update_task_due(conn: &Connection, id: i64, due_at: i64) -> result::Result<(), Error> {
+    match conn.execute(
+        "UPDATE tasks SET due_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![due_at, id],
+    )? {
+        0 => Err(anyhow!("No task with id {id}")),
+        _ => Ok(()),
+    }
+}
+
+fn update_task_status_with_note(
+    conn: &Connection,
+    id: i64,
+    status: Status,
+    note: &str,
+) -> result::Result<(), Error> {
+    let tx = conn.unchecked_transaction()?;
+    update_task_status(&tx, id, status)?;
+    append_task_notes(&tx, id, note)?;
+    Ok(tx.commit()?)
+}
+
+pub fn mark_task_done_with_note(conn: &Connection, id: i64, note: &str) {
+    match update_task_status_with_note(conn, id, Status::Completed, note) {
+        Ok(_) => println!("Marked task [{id}] complete"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn add_task_note(conn: &Connection, id: i64, text: &str) {
+    match append_task_notes(conn, id, text) {
+        Ok(_) => println!("Added note to task {id}"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn mark_task_blocked(conn: &Connection, id: i64) {
+    match update_task_status(conn, id, Status::Blocked) {
+        Ok(_) => println!("Blocked task {id}"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn mark_task_blocked_with_reason(conn: &Connection, id: i64, reason: &str) {
+    match update_task_status_with_note(conn, id, Status::Blocked, reason) {
+        Ok(_) => println!("Blocked task {id}"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn mark_task_pending(conn: &Connection, task: Task) {
+    match update_task_status(conn, task.id, Status::Pending) {
+        Ok(_) => println!("Paused task {}", task.id),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+/// Moves every in progress task back to pending, returning how many were paused.
+pub fn pause_all_active(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "UPDATE tasks SET status = ?1 WHERE status = ?2 AND deleted_at IS NULL",
+        params![Status::Pending, Status::InProgress],
+    )
+}
+
+pub fn mark_task_cancelled(conn: &Connection, id: i64) {
+    match update_task_status(conn, id, Status::Cancelled) {
+        Ok(_) => println!("Cancelled task {id}"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn mark_task_cancelled_with_reason(conn: &Connection, id: i64, reason: &str) {
+    let note = format!("[Cancelled: {reason}]");
+
+    match update_task_status_with_note(conn, id, Status::Cancelled, &note) {
+        Ok(_) => println!("Cancelled task {id}"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+/// Highest priority first, then the closest due date, then the oldest task.
+/// `id` settles tasks created within the same second.
+const NEXT_TASKS_SQL: &str = "
+    SELECT id
+    FROM tasks
+    WHERE status = ?1
+    ORDER BY priority DESC, due_at NULLS LAST, created_at, id
+    LIMIT ?2;";
+
+pub fn select_next_task(conn: &Connection, id: Option<i64>) {
+    let next_id = match id {
+        Some(id) => Ok(id),
+        None => conn.query_row(NEXT_TASKS_SQL, params![Status::Pending, 1], |row| {
+            row.get(0)
+        }),
+    };
+
+    match next_id {
+        Ok(id) => match update_task_status(conn, id, Status::InProgress) {
+            Ok(_) => println!("Set task {id} to in progress."),
+            Err(_) => println!("No tasks to set to next."),
+        },
+        Err(rusqlite::Error::QueryReturnedNoRows) => println!("No tasks waiting. All done!"),
+        Err(_) => panic!(),
+    }
+}
+
+/// How `td next` picks the task to promote.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// Highest priority first, then the closest due date
+    #[default]
+    Priority,
+    /// Closest due date first, tasks without one last
+    Due,
+    /// Oldest task first
+    Oldest,
+    /// Any pending task
+    Random,
+    /// Highest score first, see `td score`
+    Score,
+    /// Tasks due today first, then as `Priority`
+    TodayFirst,
+}
+
+fn next_task_id(
+    conn: &Connection,
+    strategy: SelectionStrategy,
+) -> result::Result<Option<i64>, Error> {
+    let order = match strategy {
+        SelectionStrategy::Priority => "priority DESC, due_at NULLS LAST, created_at, id",
+        SelectionStrategy::Due => "due_at NULLS LAST, priority DESC, created_at, id",
+        SelectionStrategy::Oldest => "created_at, id",
+        SelectionStrategy::Random => "RANDOM()",
+        SelectionStrategy::Score => {
+            return Ok(compute_scores(conn)?.first().map(|(task, _)| task.id));
+        }
+        SelectionStrategy::TodayFirst => {
+            let pending = query_tasks(conn, &ListOptions::default().filter(ListFilter::Pending))?;
+            let sorted = sort_today_due_first(pending, day_bounds(0)?);
+            return Ok(sorted.first().map(|task| task.id));
+        }
+    };
+
+    Ok(conn.query_row(
+        &format!("SELECT id FROM tasks WHERE status = ?1 AND deleted_at IS NULL ORDER BY {order} LIMIT 1;"),
+        [Status::Pending],
+        |row| row.get(0),
+    )
+    .optional()?)
+}
+
+/// Tasks due within `today`, a range of timestamps, first. Both parts are in
+/// selection order, see `impl Ord for Task`.
+pub fn sort_today_due_first(tasks: Vec<Task>, today: (i64, i64)) -> Vec<Task> {
+    let (start, end) = today;
+    let (mut due_today, mut other): (Vec<Task>, Vec<Task>) = tasks
+        .into_iter()
+        .partition(|task| task.due_at.is_some_and(|due| (start..=end).contains(&due)));
+
+    due_today.sort();
+    other.sort();
+    due_today.extend(other);
+    due_today
+}
+
+/// Promotes `id`, or the next pending task by `strategy` when not given,
+/// returning the promoted id. `None` when no task is waiting.
+pub fn select_next_with_strategy(
+    conn: &Connection,
+    strategy: SelectionStrategy,
+    id: Option<i64>,
+) -> result::Result<Option<i64>, Error> {
+    let id = match id {
+        Some(id) => Some(id),
+        None => next_task_id(conn, strategy)?,
+    };
+
+    if let Some(id) = id {
+        update_task_status(conn, id, Status::InProgress)?;
+    }
+
+    Ok(id)
+}
+
+/// Promotes the next task unless one is already in progress. The active task
+/// has to be re-read here, as completing a task may have just freed the slot.
+pub fn select_next_task_if_idle(conn: &Connection) {
+    if get_current_active_task(conn).is_none() {
+        select_next_task(conn, None)
+    }
+}
+
+/// Promotes a random pending task to in progress, returning its id.
+pub fn select_random_task(conn: &Connection) -> result::Result<Option<i64>, Error> {
+    let id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM tasks WHERE status = ?1 ORDER BY RANDOM() LIMIT 1;",
+            [Status::Pending],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(id) = id {
+        update_task_status(conn, id, Status::InProgress)?;
+    }
+
+    Ok(id)
+}
+
+/// Copies the text, priority, tags, assignee and estimate of a task into a new
+/// in progress task, returning the new id.
+pub fn repeat_task(conn: &Connection, id: i64) -> result::Result<i64, Error> {
+    let tx = conn.unchecked_transaction()?;
+
+    let new_id = tx
+        .query_row(
+            "INSERT INTO tasks (task, status, priority, created_at, tags, assignee, estimate_minutes)
+            SELECT task, ?2, priority, ?3, tags, assignee, estimate_minutes
+            FROM tasks WHERE id = ?1 AND deleted_at IS NULL
+            RETURNING id;",
+            params![id, Status::InProgress, Utc::now().timestamp()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("No task with id {id}"))?;
+
+    tx.commit()?;
+    Ok(new_id)
+}
+
+/// Fields passed explicitly to `td add --template`, taking precedence over the template.
+#[derive(Debug, Default)]
+pub struct AddOverrides {
+    pub task: Option<String>,
+    pub priority: Option<i64>,
+    pub due: Option<String>,
+    pub tags: Option<String>,
+    pub assignee: Option<String>,
+    pub estimate_minutes: Option<i64>,
+}
+
+/// Adds a pending task with the text, priority, tags and estimate of an
+/// existing task, unless given in `overrides`. Returns the new id.
+pub fn add_from_template(
+    conn: &Connection,
+    source_id: i64,
+    overrides: AddOverrides,
+) -> result::Result<i64, Error> {
+    let source =
+        get_task_by_id(conn, source_id)?.ok_or_else(|| anyhow!("No task with id {source_id}"))?;
+
+    let id = add_task(
+        conn,
+        overrides.task.as_deref().unwrap_or(&source.task),
+        overrides.priority.or(Some(source.priority)),
+        overrides.due,
+        overrides.tags.or(source.tags),
+        overrides.assignee,
+        overrides.estimate_minutes.or(source.estimate_minutes),
+    )?;
+    Ok(id)
+}
+
+/// Promotes the next `n` pending tasks to in progress, returning their ids in selection order.
+pub fn next_batch(conn: &Connection, n: usize) -> result::Result<Vec<i64>, Error> {
+    let tx = conn.unchecked_transaction()?;
+
+    let ids: Vec<i64> = tx
+        .prepare(NEXT_TASKS_SQL)?
+        .query_map(params![Status::Pending, n], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+
+    for id in &ids {
+        update_task_status(&tx, *id, Status::InProgress)?;
+    }
+
+    tx.commit()?;
+    Ok(ids)
+}
+
+pub fn select_next_batch(conn: &Connection, n: usize) {
+    match next_batch(conn, n) {
+        Ok(ids) if ids.is_empty() => println!("No tasks waiting. All done!"),
+        Ok(ids) => ids
+            .iter()
+            .for_each(|id| println!("Set task {id} to in progress.")),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn get_task_by_id(conn: &Connection, id: i64) -> Result<Option<Task>> {
+    conn.query_row(
+        "SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL;",
+        [id],
+        |row| Task::try_from(row),
+    )
+    .optional()
+}
+
+/// The task `show` displays: the given task if any, otherwise the active one.
+pub fn task_to_show(conn: &Connection, id: Option<i64>) -> result::Result<Option<Task>, Error> {
+    match id {
+        Some(id) => match get_task_by_id(conn, id)? {
+            Some(task) => Ok(Some(task)),
+            None => Err(anyhow!("No task with id {id}")),
+        },
+        None => Ok(get_current_active_task(conn)),
+    }
+}
+
+/// The most recently added task.
+pub fn get_last_added(conn: &Connection) -> Result<Option<Task>> {
+    conn.query_row(
+        "SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY id DESC LIMIT 1;",
+        [],
+        |row| Task::try_from(row),
+    )
+    .optional()
+}
+
+pub fn get_current_active_task(conn: &Connection) -> Option<Task> {
+    conn.query_row(
+        "SELECT *
+        FROM tasks
+        WHERE status = ?1
+        LIMIT 1;",
+        [Status::InProgress],
+        |row| Task::try_from(row),
+    )
+    .ok()
+}
+
+pub fn task_age_hours(task: &Task) -> f64 {
+    task_age_hours_at(task, Utc::now().timestamp())
+}
+
+fn task_age_hours_at(task: &Task, now: i64) -> f64 {
+    (now - task.created_at) as f64 / 3600.0
+}
+
+/// Whole days since the task was created.
+pub fn age_days(task: &Task) -> u32 {
+    age_days_at(task, Utc::now().timestamp())
+}
+
+fn age_days_at(task: &Task, now: i64) -> u32 {
+    ((now - task.created_at) / 86400).max(0) as u32
+}
+
+fn is_stale_at(task: &Task, threshold_hours: i64, now: i64) -> bool {
+    now - task.created_at > threshold_hours * 3600
+}
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// `priority * 10 + overdue_days * 5 - days_old`, higher scores come first.
+pub fn score(priority: i64, due_at: Option<i64>, created_at: i64, now: i64) -> f64 {
+    let overdue_days = due_at.map_or(0.0, |due_at| (now - due_at).max(0) as f64 / SECONDS_PER_DAY);
+    let days_old = (now - created_at) as f64 / SECONDS_PER_DAY;
+
+    priority as f64 * 10.0 + overdue_days * 5.0 - days_old
+}
+
+/// Pending tasks with their score, highest first.
+pub fn compute_scores(conn: &Connection) -> Result<Vec<(Task, f64)>> {
+    let now = Utc::now().timestamp();
+
+    let mut scores: Vec<(Task, f64)> =
+        query_tasks(conn, &ListOptions::default().filter(ListFilter::Pending))?
+            .into_iter()
+            .map(|task| {
+                let score = score(task.priority, task.due_at, task.created_at, now);
+                (task, score)
+            })
+            .collect();
+
+    scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    Ok(scores)
+}
+
+pub fn print_scores(conn: &Connection) {
+    let scores = match compute_scores(conn) {
+        Ok(scores) => scores,
+        Err(err) => return tracing::error!("{err:?}"),
+    };
+
+    print!("SCORE   ");
+    print_task_header(DEFAULT_COLUMNS);
+    for (task, score) in scores {
+        println!("{score:<7.1} {task}");
+    }
+}
+
+pub fn warn_if_stale(task: &Task, threshold_hours: i64) {
+    if is_stale_at(task, threshold_hours, Utc::now().timestamp()) {
+        tracing::warn!(
+            "task [{}] has been in-progress for {:.1} days",
+            task.id,
+            task_age_hours(task) / 24.0
+        )
+    }
+}
+
+/// Completed tasks out of all tasks, overall and per tag. Cancelled tasks
+/// are left out, tags are listed in alphabetical order as (tag, completed, total).
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ProgressReport {
+    total: usize,
+    completed: usize,
+    by_tag: Vec<(String, usize, usize)>,
+}
+
+pub fn compute_progress(conn: &Connection) -> Result<ProgressReport> {
+    let mut report = ProgressReport {
+        total: 0,
+        completed: 0,
+        by_tag: vec![],
+    };
+    let mut by_tag: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+
+    for task in query_tasks(conn, &ListOptions::default().filter(ListFilter::All))? {
+        if task.status == Status::Cancelled {
+            continue;
+        }
+        let completed = usize::from(task.status == Status::Completed);
+
+        report.total += 1;
+        report.completed += completed;
+        for tag in task.tag_list() {
+            let counts = by_tag.entry(tag.to_string()).or_default();
+            counts.0 += completed;
+            counts.1 += 1;
+        }
+    }
+
+    report.by_tag = by_tag
+        .into_iter()
+        .map(|(tag, (completed, total))| (tag, completed, total))
+        .collect();
+    Ok(report)
+}
+
+/// Share of `completed` in `total` as a whole percentage, 0 when there are no tasks.
+fn percent(completed: usize, total: usize) -> usize {
+    match total {
+        0 => 0,
+        _ => completed * 100 / total,
+    }
+}
+
+pub fn print_progress(conn: &Connection, json: bool) {
+    let report = match compute_progress(conn) {
+        Ok(report) => report,
+        Err(err) => return tracing::error!("{err:?}"),
+    };
+
+    if json {
+        return match serde_json::to_string(&report) {
+            Ok(json) => println!("{json}"),
+            Err(err) => tracing::error!("{err:?}"),
+        };
+    }
+
+    println!(
+        "Completed {} / {} tasks ({}%)",
+        report.completed,
+        report.total,
+        percent(report.completed, report.total)
+    );
+    if !report.by_tag.is_empty() {
+        println!();
+    }
+    for (tag, completed, total) in &report.by_tag {
+        println!(
+            "  #{tag}: {completed} / {total} ({}%)",
+            percent(*completed, *total)
+        );
+    }
+}
+
+/// Task counts per priority, index 0 being priority 1.
+pub fn priority_histogram(conn: &Connection, all: bool) -> Result<[usize; 5]> {
+    let sql = match all {
+        true => "SELECT priority, COUNT(*) FROM tasks WHERE deleted_at IS NULL GROUP BY priority;",
+        false => {
+            "SELECT priority, COUNT(*) FROM tasks WHERE status IN (0, 1, 4) GROUP BY priority;"
+        }
+    };
+
+    let mut statement = conn.prepare(sql)?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, usize>(1)?))
+    })?;
+
+    let mut histogram = [0; 5];
+    for row in rows {
+        let (priority, count) = row?;
+        histogram[priority.clamp(1, 5) as usize - 1] += count;
+    }
+
+    Ok(histogram)
+}
+
+/// Open tasks whose estimate has run out, see `compute_eta`.
+pub fn count_past_eta(conn: &Connection, now: i64) -> Result<usize> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM tasks
+        WHERE status IN (0, 1, 4) AND created_at + estimate_minutes * 60 < ?1",
+        [now],
+        |row| row.get(0),
+    )
+}
+
+pub fn print_priority_histogram(conn: &Connection, all: bool) {
+    let histogram = match priority_histogram(conn, all) {
+        Ok(histogram) => histogram,
+        Err(err) => return tracing::error!("{err:?}"),
+    };
+
+    let width = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .unwrap_or(80);
+
+    for (i, count) in histogram.iter().enumerate().rev() {
+        let priority = i as i64 + 1;
+        let bar = "█".repeat((*count).min(width / 5));
+        println!(
+            "{priority} [{:^3}] {bar} {count} tasks",
+            priority_symbol(priority)
+        );
+    }
+
+    match count_past_eta(conn, Utc::now().timestamp()) {
+        Ok(0) => {}
+        Ok(n) => println!("\n⏱ {n} tasks past their ETA"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+pub fn assign_task(conn: &Connection, id: i64, assignee: &str) -> result::Result<(), Error> {
+    match conn.execute(
+        "UPDATE tasks SET assignee = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![assignee, id],
+    ) {
+        Ok(0) => Err(anyhow!("No task with id {id}")),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ids and tags of the tasks tagged exactly `tag`. `instr` instead of `LIKE`,
+/// which ignores case and reads `_` and `%` as wildcards.
+fn tasks_with_tag(conn: &Connection, tag: &str) -> Result<Vec<(i64, String)>> {
+    conn.prepare(
+        "SELECT id, tags FROM tasks
+        WHERE instr(',' || tags || ',', ',' || ?1 || ',') > 0 AND deleted_at IS NULL;",
+    )?
+    .query_map([tag], |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect()
+}
+
+/// Replaces the tags of each task tagged `tag` with `update(tags)`, `NULL`
+/// when none are left. Returns how many tasks were updated.
+fn update_tags(conn: &Connection, tag: &str, update: impl Fn(&str) -> String) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let tasks = tasks_with_tag(&tx, tag)?;
+    for (id, tags) in &tasks {
+        let tags = update(tags);
+        tx.execute(
+            "UPDATE tasks SET tags = NULLIF(?1, '') WHERE id = ?2",
+            params![tags, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(tasks.len())
+}
+
+/// Renames `old` to `new`, dropping `old` where the task was tagged `new` already.
+pub fn rename_tag(conn: &Connection, old: &str, new: &str) -> Result<usize> {
+    update_tags(conn, old, |tags| {
+        let mut renamed = vec![];
+        for tag in tags
+            .split(',')
+            .map(|tag| if tag == old { new } else { tag })
+        {
+            if !renamed.contains(&tag) {
+                renamed.push(tag);
+            }
+        }
+        renamed.join(",")
+    })
+}
+
+pub fn remove_tag(conn: &Connection, tag: &str) -> Result<usize> {
+    update_tags(conn, tag, |tags| {
+        let kept: Vec<&str> = tags.split(',').filter(|&other| other != tag).collect();
+        kept.join(",")
+    })
+}
+
+/// Moves completed, and optionally cancelled, tasks into `tasks_archive`.
+pub fn archive_tasks(conn: &Connection, include_cancelled: bool) -> Result<usize> {
+    let statuses = match include_cancelled {
+        true => "(2, 3)",
+        false => "(2)",
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    let n = tx.execute(
+        &format!(
+            "INSERT INTO tasks_archive ({TASK_COLUMNS}, archived_at)
+            SELECT {TASK_COLUMNS}, ?1 FROM tasks
+            WHERE status IN {statuses} AND deleted_at IS NULL;"
+        ),
+        [Utc::now().timestamp()],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM tasks WHERE status IN {statuses} AND deleted_at IS NULL;"),
+        [],
+    )?;
+    tx.commit()?;
+
+    Ok(n)
+}
+
+pub fn list_archived(conn: &Connection) -> Result<Vec<Task>> {
+    let mut statement = conn.prepare("SELECT * FROM tasks_archive ORDER BY archived_at, id;")?;
+    select_to_tasks(&mut statement)
+}
+
+fn write_archived_tasks(conn: &Connection, out: &mut dyn Write) -> result::Result<(), Error> {
+    let tasks = list_archived(conn)?;
+    Ok(write_tasks(
+        out,
+        &tasks,
+        DisplayFormat::Table,
+        ARCHIVE_COLUMNS,
+        None,
+    )?)
+}
+
+pub fn print_archived_tasks(conn: &Connection, out: &mut dyn Write) {
+    if let Err(err) = write_archived_tasks(conn, out) {
+        tracing::error!("{err:?}")
+    }
+}
+
+/// Soft deletes cancelled tasks, see `purge_deleted` and `restore_task`.
+pub fn collect_garbage(conn: &Connection) {
+    match soft_delete_cancelled(conn) {
+        Ok(n) => println!("Deleted {n} tasks. Hint: use `td gc --purge` to remove them for good"),
+        Err(err) => tracing::error!("{err:?}"),
+    }
+}
+
+fn soft_delete_cancelled(conn: &Connection) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let now = Utc::now().timestamp();
+
+    let n = tx.execute(
+        "UPDATE tasks SET deleted_at = ?2 WHERE status = ?1 AND deleted_at IS NULL",
+        params![Status::Cancelled, now],
+    )?;
+    log_gc(&tx, now, n)?;
+
+    tx.commit()?;
+    Ok(n)
+}
+
+fn log_gc(conn: &Connection, run_at: i64, deleted: usize) -> Result<()> {
+    conn.execute(
+        "INSERT INTO gc_log (run_at, deleted_count) VALUES (?1, ?2)",
+        params![run_at, deleted],
+    )?;
+    Ok(())
+}
+
+/// When `gc` last ran, if ever.
+pub fn last_gc_at(conn: &Connection) -> Result<Option<i64>> {
+    conn.query_row("SELECT MAX(run_at) FROM gc_log", [], |row| row.get(0))
+}
+
+/// Permanently removes a task right away, without going through `gc`.
+pub fn delete_task(conn: &Connection, id: i64) -> result::Result<usize, Error> {
+    match conn.execute("DELETE FROM tasks WHERE id = ?1", [id])? {
+        0 => Err(anyhow!("No task with id {id}")),
+        n => Ok(n),
+    }
+}
+
+/// Permanently removes soft deleted tasks.
+pub fn purge_deleted(conn: &Connection) -> Result<usize> {
+    conn.execute("DELETE FROM tasks WHERE deleted_at IS NOT NULL", [])
+}
+
+/// Brings back a soft deleted task.
+pub fn restore_task(conn: &Connection, id: i64) -> result::Result<(), Error> {
+    match conn.execute(
+        "UPDATE tasks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+        [id],
+    )? {
+        0 => Err(anyhow!("No deleted task with id {id}")),
+        _ => Ok(()),
+    }
+}
+
+/// Deletes completed and cancelled tasks finished before `cutoff`. Tasks finished
+/// before `completed_at` was tracked fall back to their creation time.
+pub fn collect_garbage_older_than(conn: &Connection, cutoff: i64) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let now = Utc::now().timestamp();
+
+    let n = tx.execute(
+        "UPDATE tasks SET deleted_at = ?2
+        WHERE status IN (2, 3) AND deleted_at IS NULL AND COALESCE(completed_at, created_at) < ?1",
+        [cutoff, now],
+    )?;
+    log_gc(&tx, now, n)?;
+
+    tx.commit()?;
+    Ok(n)
+}
+
+/// Completed tasks finished more than `days` ago, or all of them when `days` is `None`.
+const COMPLETED_OLDER_THAN: &str =
+    "status = 2 AND deleted_at IS NULL AND COALESCE(completed_at, created_at) < ?1";
+
+fn completed_cutoff(days: Option<u32>) -> i64 {
+    days.map_or(i64::MAX, |days| {
+        Utc::now().timestamp() - i64::from(days) * 86400
+    })
+}
+
+/// Number of tasks `purge_completed_older_than` would remove.
+pub fn count_completed_older_than(conn: &Connection, days: Option<u32>) -> Result<usize> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM tasks WHERE {COMPLETED_OLDER_THAN}"),
+        [completed_cutoff(days)],
+        |row| row.get(0),
+    )
+}
+
+/// Permanently removes completed tasks finished more than `days` ago, or all
+/// completed tasks when `days` is `None`.
+pub fn purge_completed_older_than(conn: &Connection, days: Option<u32>) -> Result<usize> {
+    conn.execute(
+        &format!("DELETE FROM tasks WHERE {COMPLETED_OLDER_THAN}"),
+        [completed_cutoff(days)],
+    )
+}
+
+#[cfg(test)]
+pub fn init_test_db() -> Connection {
+    open_in_memory().unwrap()
+}
+
+#[cfg(test)]
+fn get_single_task(conn: &Connection) -> Task {
+    conn.query_row("SELECT * FROM tasks", [], |row| Task::try_from(row))
+        .unwrap()
+}
+
+#[cfg(test)]
+fn count_tasks(conn: &Connection) -> usize {
+    let mut statement = conn
+        .prepare("SELECT COUNT(*) FROM tasks WHERE deleted_at IS NULL")
+        .unwrap();
+    statement.query_row([], |row| row.get(0)).unwrap()
+}
+
+#[cfg(test)]
+macro_rules! add_task {
+    ($conn:expr, $task:expr) => {
+        add_task($conn, $task, None, None, None, None, None).unwrap()
+    };
+    ($conn:expr, $task:expr, $priority:expr) => {
+        add_task($conn, $task, Some($priority), None, None, None, None).unwrap()
+    };
+    ($conn:expr, $task:expr, $priority:expr, $due:expr) => {
+        add_task($conn, $task, Some($priority), Some($due), None, None, None).unwrap()
+    };
+}
+
+#[test]
+fn test_add_tasks() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+
+    assert_eq!(count_tasks(&conn), 1);
+
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+
+    assert_eq!(count_tasks(&conn), 4);
+}
+
+#[test]
+fn test_mark_done() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    mark_task_done(&conn, 1);
+
+    let task = get_single_task(&conn);
 
     assert_eq!(task.status, Status::Completed)
 }
 
 #[test]
-fn test_select_next_task() {
+fn test_select_next_task() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    select_next_task(&conn, None);
+
+    let task = get_single_task(&conn);
+
+    assert_eq!(task.status, Status::InProgress)
+}
+
+#[test]
+fn test_select_next_task_from_multiple() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task", 5); // id 3
+    add_task!(&conn, "Test task", 4); // id 4
+
+    select_next_task(&conn, None);
+
+    let task = conn
+        .query_row("SELECT * FROM tasks WHERE status = 1;", [], |row| {
+            Task::try_from(row)
+        })
+        .unwrap();
+
+    assert_eq!(task.status, Status::InProgress);
+    assert_eq!(task.id, 3);
+}
+
+#[test]
+fn test_no_next_task_to_select() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    mark_task_done(&conn, 1);
+    select_next_task(&conn, None);
+
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM tasks WHERE status = 1;", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+
+    assert_eq!(count, 0)
+}
+
+#[test]
+fn test_cancel_and_gc() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+
+    mark_task_cancelled(&conn, 1);
+    collect_garbage(&conn);
+
+    assert_eq!(count_tasks(&conn), 0)
+}
+
+#[test]
+fn test_gc_deleted_tasks_are_left_alone() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".to_string()),
+        None,
+        None,
+    )
+    .unwrap();
+    mark_task_cancelled(&conn, 1);
+    collect_garbage(&conn);
+    // Deleted tasks keep their row, an in progress one must stay as it was
+    conn.execute("UPDATE tasks SET status = 1 WHERE id = 1;", [])
+        .unwrap();
+    let deleted = || {
+        conn.query_row("SELECT * FROM tasks WHERE id = 1", [], |row| {
+            Task::try_from(row)
+        })
+        .unwrap()
+    };
+    let before = deleted();
+
+    for err in [
+        append_task_notes(&conn, 1, "Note").unwrap_err(),
+        rename_task(&conn, 1, "Renamed").unwrap_err(),
+        assign_task(&conn, 1, "alice").unwrap_err(),
+    ] {
+        assert_eq!(err.to_string(), "No task with id 1");
+    }
+    assert_eq!(rename_tag(&conn, "work", "home").unwrap(), 0);
+    assert_eq!(remove_tag(&conn, "work").unwrap(), 0);
+    assert_eq!(pause_all_active(&conn).unwrap(), 0);
+    assert_eq!(deleted(), before);
+}
+
+#[test]
+fn test_gc_restore_and_purge() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    mark_task_cancelled(&conn, 1);
+    mark_task_cancelled(&conn, 2);
+    collect_garbage(&conn);
+
+    let all = ListOptions::default().filter(ListFilter::All);
+    let ids = |conn| -> Vec<i64> {
+        query_tasks(conn, &all)
+            .unwrap()
+            .iter()
+            .map(|task| task.id)
+            .collect()
+    };
+
+    assert_eq!(ids(&conn), [3]);
+    assert_eq!(get_task_by_id(&conn, 1).unwrap(), None);
+    assert!(update_task_status(&conn, 1, Status::Pending).is_err());
+
+    restore_task(&conn, 1).unwrap();
+    assert!(restore_task(&conn, 1).is_err());
+    assert!(restore_task(&conn, 3).is_err());
+    assert_eq!(ids(&conn), [1, 3]);
+
+    assert_eq!(purge_deleted(&conn).unwrap(), 1);
+    assert!(restore_task(&conn, 2).is_err());
+    assert_eq!(
+        conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get::<_, i64>(0))
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn test_list_reverse() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task", 3); // id 2
+    add_task!(&conn, "Test task", 5); // id 3
+
+    let tasks = query_tasks(&conn, &ListOptions::default().filter(ListFilter::Active)).unwrap();
+    let reversed = query_tasks(
+        &conn,
+        &ListOptions::default()
+            .filter(ListFilter::Active)
+            .reverse(true),
+    )
+    .unwrap();
+
+    assert_eq!(tasks.first().unwrap().id, 3);
+    assert_eq!(tasks.last().unwrap().id, 1);
+    assert_eq!(reversed.first().unwrap().id, 1);
+    assert_eq!(reversed.last().unwrap().id, 3);
+}
+
+#[test]
+fn test_list_all_reverse() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_done(&conn, 2);
+
+    let tasks = query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+    let reversed = query_tasks(
+        &conn,
+        &ListOptions::default().filter(ListFilter::All).reverse(true),
+    )
+    .unwrap();
+
+    assert_eq!(tasks.first().unwrap().id, 1);
+    assert_eq!(reversed.first().unwrap().id, 2);
+}
+
+#[test]
+fn test_get_last_added() {
+    let conn = init_test_db();
+
+    assert_eq!(get_last_added(&conn).unwrap(), None);
+
+    add_task!(&conn, "First"); // id 1
+    add_task!(&conn, "Second"); // id 2
+    add_task!(&conn, "Third"); // id 3
+
+    let last = get_last_added(&conn).unwrap().unwrap();
+    assert_eq!((last.id, last.task.as_str()), (3, "Third"));
+
+    mark_task_cancelled(&conn, 3);
+    collect_garbage(&conn);
+
+    assert_eq!(get_last_added(&conn).unwrap().unwrap().id, 2);
+}
+
+#[test]
+fn test_update_task_due() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    update_task_due(&conn, 1, 1_000_000).unwrap();
+
+    assert_eq!(get_single_task(&conn).due_at, Some(1_000_000));
+    assert!(update_task_due(&conn, 42, 1_000_000).is_err());
+}
+
+#[test]
+fn test_sort_today_due_first() {
+    let conn = init_test_db();
+    let day = |days: u64| day_bounds(days).unwrap().0;
+
+    add_task!(&conn, "Next week", 5); // id 1
+    add_task!(&conn, "Today", 1); // id 2
+    add_task!(&conn, "Tomorrow", 4); // id 3
+    add_task!(&conn, "Today", 3); // id 4
+    add_task!(&conn, "No due date", 2); // id 5
+    for (id, due) in [(1, day(7)), (2, day(0)), (3, day(1)), (4, day(0) + 3600)] {
+        update_task_due(&conn, id, due).unwrap();
+    }
+
+    let tasks = query_tasks(&conn, &ListOptions::default()).unwrap();
+    let ids: Vec<i64> = sort_today_due_first(tasks, day_bounds(0).unwrap())
+        .iter()
+        .map(|task| task.id)
+        .collect();
+
+    assert_eq!(ids, [4, 2, 1, 3, 5]);
+    assert_eq!(
+        next_task_id(&conn, SelectionStrategy::TodayFirst).unwrap(),
+        Some(4)
+    );
+
+    let opts = ListOptions::default()
+        .today_due_first(Some(day_bounds(0).unwrap()))
+        .limit(Some(3));
+    let ids: Vec<i64> = query_tasks(&conn, &opts)
+        .unwrap()
+        .iter()
+        .map(|task| task.id)
+        .collect();
+
+    assert_eq!(ids, [4, 2, 1]);
+}
+
+#[test]
+fn test_select_next_with_strategy() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+
+    add_task!(&conn, "Test task", 5); // id 1
+    add_task!(&conn, "Test task", 1, "1.1.2020".to_string()); // id 2
+    add_task!(&conn, "Test task", 2); // id 3
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET created_at = {now} WHERE id = 1;
+        UPDATE tasks SET created_at = {now} WHERE id = 2;
+        UPDATE tasks SET created_at = {} WHERE id = 3;",
+        now - 400 * 86400
+    ))
+    .unwrap();
+
+    let next = |strategy| next_task_id(&conn, strategy).unwrap();
+
+    // Priority 5 wins by priority, 2 has a due date, 3 is oldest and 2 is
+    // overdue by years, which outweighs any priority in the score
+    assert_eq!(next(SelectionStrategy::Priority), Some(1));
+    assert_eq!(next(SelectionStrategy::Due), Some(2));
+    assert_eq!(next(SelectionStrategy::Oldest), Some(3));
+    assert_eq!(next(SelectionStrategy::Score), Some(2));
+    assert!(next(SelectionStrategy::Random).is_some());
+
+    assert_eq!(
+        select_next_with_strategy(&conn, SelectionStrategy::Oldest, None).unwrap(),
+        Some(3)
+    );
+    assert_eq!(
+        get_task_by_id(&conn, 3).unwrap().unwrap().status,
+        Status::InProgress
+    );
+    assert_eq!(
+        select_next_with_strategy(&conn, SelectionStrategy::Oldest, Some(1)).unwrap(),
+        Some(1)
+    );
+
+    mark_tasks_done(&conn, &[1, 2, 3]);
+    assert_eq!(
+        select_next_with_strategy(&conn, SelectionStrategy::Due, None).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_task_ord_matches_next_tasks_sql() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 3); // id 1
+    add_task!(&conn, "Test task", 5, "1.1.2030".to_string()); // id 2
+    add_task!(&conn, "Test task", 3, "2.1.2020".to_string()); // id 3
+    add_task!(&conn, "Test task", 5); // id 4
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string()); // id 5
+    add_task!(&conn, "Test task", 1); // id 6
+    add_task!(&conn, "Test task", 3); // id 7
+    conn.execute_batch("UPDATE tasks SET created_at = 1000 - id * 10 WHERE id IN (1, 7);")
+        .unwrap();
+
+    let ids = |tasks: &[Task]| tasks.iter().map(|t| t.id).collect::<Vec<_>>();
+    let expected = select_to_tasks(
+        &mut conn
+            .prepare(
+                "SELECT * FROM tasks ORDER BY priority DESC, due_at NULLS LAST, created_at, id",
+            )
+            .unwrap(),
+    )
+    .unwrap();
+
+    let mut tasks = expected.clone();
+    tasks.reverse();
+    tasks.swap(1, 4);
+    tasks.sort_unstable();
+
+    assert_eq!(ids(&tasks), ids(&expected));
+    assert_eq!(ids(&tasks), [2, 4, 5, 3, 7, 1, 6]);
+}
+
+#[test]
+fn test_stale_boundary() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    let task = get_single_task(&conn);
+    let day = 24 * 3600;
+
+    assert_eq!(task_age_hours_at(&task, task.created_at + day), 24.0);
+    assert!(!is_stale_at(&task, 24, task.created_at + day));
+    assert!(is_stale_at(&task, 24, task.created_at + day + 1));
+}
+
+#[test]
+fn test_age_days() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    let task = get_single_task(&conn);
+    let day = 24 * 3600;
+
+    assert_eq!(age_days_at(&task, task.created_at), 0);
+    assert_eq!(age_days_at(&task, task.created_at + day - 1), 0);
+    assert_eq!(age_days_at(&task, task.created_at + 3 * day), 3);
+    assert_eq!(age_days_at(&task, task.created_at + 31 * day + 10), 31);
+    assert_eq!(age_days_at(&task, task.created_at - day), 0);
+}
+
+#[test]
+fn test_mark_done_with_note() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    mark_task_done_with_note(&conn, 1, "Shipped it");
+
+    let task = get_task_by_id(&conn, 1).unwrap().unwrap();
+
+    assert_eq!(task.status, Status::Completed);
+    assert_eq!(task.notes.as_deref(), Some("Shipped it"));
+}
+
+#[test]
+fn test_partition_by_overdue() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string()); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task", 3, "1.1.2999".to_string()); // id 3
+    add_task!(&conn, "Test task", 3, "2.1.2020".to_string()); // id 4
+    mark_task_done(&conn, 4);
+
+    let tasks = query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+    let (overdue, rest) = partition_by_overdue(tasks);
+
+    assert_eq!(overdue.iter().map(|t| t.id).collect::<Vec<_>>(), [1]);
+    assert_eq!(rest.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 3, 4]);
+}
+
+#[test]
+fn test_list_in_progress() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    mark_task_done(&conn, 1);
+    select_next_task(&conn, Some(3));
+
+    let tasks = query_tasks(
+        &conn,
+        &ListOptions::default().filter(ListFilter::InProgress),
+    )
+    .unwrap();
+
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, 3);
+    assert_eq!(tasks[0].status, Status::InProgress);
+}
+
+#[test]
+fn test_block_task() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_blocked(&conn, 1);
+    mark_task_blocked_with_reason(&conn, 2, "Waiting on review");
+
+    let blocked = query_tasks(&conn, &ListOptions::default().filter(ListFilter::Blocked)).unwrap();
+    let active = query_tasks(&conn, &ListOptions::default().filter(ListFilter::Active)).unwrap();
+
+    assert_eq!(blocked.len(), 2);
+    assert_eq!(active.len(), 2);
+    assert_eq!(blocked[1].notes.as_deref(), Some("Waiting on review"));
+    assert_eq!(Status::try_from(4).unwrap(), Status::Blocked);
+}
+
+#[test]
+fn test_priority_histogram() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1);
+    add_task!(&conn, "Test task", 5);
+    add_task!(&conn, "Test task", 5);
+    add_task!(&conn, "Test task"); // id 4
+    add_task!(&conn, "Test task", 2); // id 5
+    mark_task_done(&conn, 5);
+
+    assert_eq!(priority_histogram(&conn, false).unwrap(), [1, 0, 1, 0, 2]);
+    assert_eq!(priority_histogram(&conn, true).unwrap(), [1, 1, 1, 0, 2]);
+}
+
+#[test]
+fn test_daily_agenda() {
+    let conn = init_test_db();
+    let today = chrono::Local::now().format("%d.%m.%Y").to_string();
+
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string()); // id 1
+    add_task!(&conn, "Test task", 3, today); // id 2
+    add_task!(&conn, "Test task", 3, "1.1.2999".to_string()); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    select_next_task(&conn, Some(4));
+
+    let agenda = daily_agenda(&conn).unwrap();
+
+    assert_eq!(agenda.overdue.iter().map(|t| t.id).collect::<Vec<_>>(), [1]);
+    assert_eq!(
+        agenda.due_today.iter().map(|t| t.id).collect::<Vec<_>>(),
+        [2]
+    );
+    assert_eq!(
+        agenda.in_progress.iter().map(|t| t.id).collect::<Vec<_>>(),
+        [4]
+    );
+}
+
+#[test]
+fn test_list_pending_only() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    select_next_task(&conn, Some(1));
+    mark_task_blocked(&conn, 3);
+
+    let pending = query_tasks(&conn, &ListOptions::default().filter(ListFilter::Pending)).unwrap();
+    let active = query_tasks(&conn, &ListOptions::default().filter(ListFilter::Active)).unwrap();
+
+    assert_eq!(pending.iter().map(|t| t.id).collect::<Vec<_>>(), [2]);
+    assert_eq!(active.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 3]);
+}
+
+#[test]
+fn test_cancel_with_reason() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    mark_task_blocked_with_reason(&conn, 1, "Waiting on review");
+    mark_task_cancelled_with_reason(&conn, 1, "No longer needed");
+
+    let task = get_task_by_id(&conn, 1).unwrap().unwrap();
+
+    assert_eq!(task.status, Status::Cancelled);
+    assert_eq!(
+        task.notes.as_deref(),
+        Some("Waiting on review\n[Cancelled: No longer needed]")
+    );
+}
+
+#[test]
+fn test_foreign_keys_enforced() {
+    let conn = init_test_db();
+
+    let result = conn.execute(
+        "INSERT INTO tasks (task, created_at, depends_on) VALUES ('Test task', 0, 42);",
+        [],
+    );
+
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("FOREIGN KEY constraint failed")
+    );
+}
+
+#[test]
+fn test_rename_and_remove_tag() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work, homework".to_string()),
+        None,
+        None,
+    )
+    .unwrap(); // id 1
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("home,work,urgent".to_string()),
+        None,
+        None,
+    )
+    .unwrap(); // id 2
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".to_string()),
+        None,
+        None,
+    )
+    .unwrap(); // id 3
+
+    assert_eq!(rename_tag(&conn, "work", "job").unwrap(), 3);
+    assert_eq!(
+        get_task_by_id(&conn, 1).unwrap().unwrap().tags.as_deref(),
+        Some("job,homework")
+    );
+    assert_eq!(
+        get_task_by_id(&conn, 2).unwrap().unwrap().tags.as_deref(),
+        Some("home,job,urgent")
+    );
+
+    assert_eq!(remove_tag(&conn, "job").unwrap(), 3);
+    assert_eq!(
+        get_task_by_id(&conn, 1).unwrap().unwrap().tags.as_deref(),
+        Some("homework")
+    );
+    assert_eq!(
+        get_task_by_id(&conn, 2).unwrap().unwrap().tags.as_deref(),
+        Some("home,urgent")
+    );
+    assert_eq!(get_task_by_id(&conn, 3).unwrap().unwrap().tags, None);
+
+    // Exact matches only, and no tag twice after a rename
+    assert_eq!(rename_tag(&conn, "HOME", "house").unwrap(), 0);
+    assert_eq!(rename_tag(&conn, "h_me", "house").unwrap(), 0);
+    assert_eq!(rename_tag(&conn, "urgent", "home").unwrap(), 1);
+    assert_eq!(
+        get_task_by_id(&conn, 2).unwrap().unwrap().tags.as_deref(),
+        Some("home")
+    );
+}
+
+#[test]
+fn test_tasks_grouped_by_tag() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work,home".to_string()),
+        None,
+        None,
+    )
+    .unwrap(); // id 1
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".to_string()),
+        None,
+        None,
+    )
+    .unwrap(); // id 2
+    add_task!(&conn, "Test task"); // id 3
+
+    let groups: Vec<(String, Vec<i64>)> = tasks_grouped_by_tag(&conn)
+        .unwrap()
+        .into_iter()
+        .map(|(tag, tasks)| (tag, tasks.iter().map(|t| t.id).collect()))
+        .collect();
+
+    assert_eq!(
+        groups,
+        [
+            ("home".to_string(), vec![1]),
+            ("work".to_string(), vec![1, 2]),
+            ("[untagged]".to_string(), vec![3]),
+        ]
+    );
+}
+
+#[test]
+fn test_next_batch() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task", 4); // id 2
+    add_task!(&conn, "Test task", 5); // id 3
+    add_task!(&conn, "Test task", 4, "1.1.2020".to_string()); // id 4
+
+    let ids = next_batch(&conn, 3).unwrap();
+
+    assert_eq!(ids, [3, 4, 2]);
+    assert_eq!(
+        query_tasks(
+            &conn,
+            &ListOptions::default().filter(ListFilter::InProgress)
+        )
+        .unwrap()
+        .len(),
+        3
+    );
+    assert_eq!(
+        get_task_by_id(&conn, 1).unwrap().unwrap().status,
+        Status::Pending
+    );
+}
+
+#[test]
+fn test_done_and_cancel_twice() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_done(&conn, 1);
+    mark_task_cancelled(&conn, 2);
+
+    let done = update_task_status(&conn, 1, Status::Completed).unwrap_err();
+    let cancelled = update_task_status(&conn, 2, Status::Cancelled).unwrap_err();
+
+    assert_eq!(done.to_string(), "Task 1 is already completed");
+    assert_eq!(cancelled.to_string(), "Task 2 is already cancelled");
+}
+
+#[test]
+#[tracing_test::traced_test]
+fn test_failed_update_logs_error() {
+    let conn = init_test_db();
+
+    mark_task_cancelled(&conn, 42);
+
+    assert!(logs_contain("ERROR"));
+    assert!(logs_contain("No rows were updated given id 42"));
+}
+
+#[test]
+fn test_list_to_file() {
+    let conn = init_test_db();
+    let path = std::env::temp_dir().join(format!("td-test-list-{}.txt", std::process::id()));
+
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string());
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".to_string()),
+        None,
+        None,
+    )
+    .unwrap();
+
+    let mut stdout = Vec::new();
+    list_tasks(&conn, &ListOptions::default(), &mut stdout);
+    list_tasks(
+        &conn,
+        &ListOptions::default(),
+        &mut std::fs::File::create(&path).unwrap(),
+    );
+
+    let written = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(!stdout.is_empty());
+    assert_eq!(written, stdout);
+}
+
+#[test]
+fn test_pending_migrations() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute(TABLE_DDL, []).unwrap();
+
+    assert_eq!(pending_migrations(&conn).unwrap(), MIGRATIONS);
+
+    conn.execute_batch(MIGRATIONS[0]).unwrap();
+    conn.pragma_update(None, "user_version", 1).unwrap();
+
+    assert_eq!(pending_migrations(&conn).unwrap(), &MIGRATIONS[1..]);
+
+    migrate(&conn).unwrap();
+
+    assert!(pending_migrations(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_archive_tasks() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    mark_task_done(&conn, 1);
+    mark_task_cancelled(&conn, 2);
+
+    assert_eq!(archive_tasks(&conn, false).unwrap(), 1);
+    assert_eq!(count_tasks(&conn), 2);
+
+    assert_eq!(archive_tasks(&conn, true).unwrap(), 1);
+    assert_eq!(count_tasks(&conn), 1);
+
+    let archived: usize = conn
+        .query_row("SELECT COUNT(*) FROM tasks_archive", [], |row| row.get(0))
+        .unwrap();
+
+    assert_eq!(archived, 2);
+}
+
+#[test]
+fn test_list_archived() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_done(&conn, 2);
+    archive_tasks(&conn, false).unwrap();
+
+    let archived = list_archived(&conn).unwrap();
+
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].id, 2);
+    assert_eq!(archived[0].status, Status::Completed);
+    assert!(archived[0].archived_at.is_some());
+    assert!(
+        get_task_by_id(&conn, 1)
+            .unwrap()
+            .unwrap()
+            .archived_at
+            .is_none()
+    );
+}
+
+#[test]
+fn test_db_info() {
+    let conn = init_test_db();
+    let mut out = Vec::new();
+
+    write_db_info(&conn, Path::new("/tmp/td.db"), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let version = out
+        .lines()
+        .find_map(|line| line.strip_prefix("SQLite version: "))
+        .unwrap();
+
+    assert!(out.contains("Database: /tmp/td.db"));
+    assert!(version.split('.').all(|part| part.parse::<u32>().is_ok()));
+    assert!(out.contains(&format!("Schema version: {}", MIGRATIONS.len())));
+}
+
+#[test]
+fn test_reinit_db() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    reinit_db(&conn).unwrap();
+
+    assert_eq!(count_tasks(&conn), 0);
+    assert!(pending_migrations(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_is_overdue_boundary() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+
+    add_task!(&conn, "Test task");
+    let mut task = get_single_task(&conn);
+
+    task.due_at = Some(now);
+    assert!(!task.is_overdue(now));
+
+    task.due_at = Some(now - 1);
+    assert!(task.is_overdue(now));
+    assert!(is_task_overdue(&task));
+}
+
+#[test]
+fn test_done_next_promotes_after_active_completed() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 5); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    select_next_task(&conn, None);
+
+    mark_task_done(&conn, 1);
+    select_next_task_if_idle(&conn);
+
+    assert_eq!(
+        get_task_by_id(&conn, 2).unwrap().unwrap().status,
+        Status::InProgress
+    );
+}
+
+#[test]
+fn test_list_options_limit() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task", 5); // id 2
+    add_task!(&conn, "Test task", 3); // id 3
+
+    let opts = ListOptions::default().limit(Some(2));
+    let tasks = query_tasks(&conn, &opts).unwrap();
+
+    assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 3]);
+}
+
+#[test]
+fn test_list_options_reverse_with_limit() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task", 5); // id 2
+    add_task!(&conn, "Test task", 3); // id 3
+
+    let opts = ListOptions::default().reverse(true).limit(Some(1));
+    let tasks = query_tasks(&conn, &opts).unwrap();
+
+    assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), [1]);
+}
+
+#[test]
+fn test_list_options_without_overdue_section() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string());
+
+    let mut with_section = Vec::new();
+    let mut without_section = Vec::new();
+    list_tasks(&conn, &ListOptions::default(), &mut with_section);
+    list_tasks(
+        &conn,
+        &ListOptions::default()
+            .filter(ListFilter::All)
+            .overdue_section(false),
+        &mut without_section,
+    );
+
+    assert!(
+        String::from_utf8(with_section)
+            .unwrap()
+            .starts_with("⚠ OVERDUE")
+    );
+    assert!(
+        !String::from_utf8(without_section)
+            .unwrap()
+            .contains("OVERDUE")
+    );
+}
+
+#[test]
+fn test_task_json_round_trip() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        Some(4),
+        Some("2.9.2025".to_string()),
+        Some("work".to_string()),
+        Some("alice".to_string()),
+        None,
+    )
+    .unwrap();
+    mark_task_blocked_with_reason(&conn, 1, "Waiting on review");
+    let task = get_single_task(&conn);
+
+    let json = serde_json::to_string(&task).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(value["status"], "blocked");
+    assert!(value["created_at"].as_str().unwrap().contains('T'));
+    assert_eq!(serde_json::from_str::<Task>(&json).unwrap(), task);
+}
+
+#[test]
+fn test_select_random_task() {
+    let conn = init_test_db();
+
+    (0..100).for_each(|_| {
+        add_task!(&conn, "Test task");
+    });
+
+    let id = select_random_task(&conn).unwrap().unwrap();
+
+    assert!((1..=100).contains(&id));
+    assert_eq!(
+        get_task_by_id(&conn, id).unwrap().unwrap().status,
+        Status::InProgress
+    );
+}
+
+#[test]
+fn test_list_by_assignee() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        None,
+        Some("alice".to_string()),
+        None,
+    )
+    .unwrap(); // id 1
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        None,
+        Some("bob".to_string()),
+        None,
+    )
+    .unwrap(); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    assign_task(&conn, 3, "alice").unwrap();
+    mark_task_done(&conn, 3);
+
+    let opts = ListOptions::default().assignee(Some("alice".to_string()));
+    let mine = query_tasks(&conn, &opts.clone().filter(ListFilter::All)).unwrap();
+    let active_mine = query_tasks(&conn, &opts).unwrap();
+
+    assert_eq!(mine.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(active_mine.iter().map(|t| t.id).collect::<Vec<_>>(), [1]);
+    assert!(assign_task(&conn, 42, "alice").is_err());
+}
+
+#[test]
+fn test_list_since_last_gc() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_cancelled(&conn, 2);
+    conn.execute("UPDATE tasks SET created_at = ?1", [now - 100])
+        .unwrap();
+    assert_eq!(last_gc_at(&conn).unwrap(), None);
+
+    collect_garbage(&conn);
+    conn.execute("UPDATE gc_log SET run_at = ?1", [now - 10])
+        .unwrap();
+    add_task!(&conn, "Test task"); // id 3
+    add_task!(&conn, "Test task"); // id 4
+
+    let deleted: i64 = conn
+        .query_row("SELECT deleted_count FROM gc_log", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(deleted, 1);
+
+    let last_gc = last_gc_at(&conn).unwrap();
+    assert_eq!(last_gc, Some(now - 10));
+
+    let opts = ListOptions::default().created_after(last_gc.map(|run_at| run_at + 1));
+    let ids: Vec<i64> = query_tasks(&conn, &opts)
+        .unwrap()
+        .iter()
+        .map(|t| t.id)
+        .collect();
+    assert_eq!(ids, [3, 4]);
+}
+
+#[test]
+fn test_collect_garbage_older_than() {
+    let conn = init_test_db();
+    let cutoff = 1_000_000;
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    add_task!(&conn, "Test task"); // id 5
+    mark_task_done(&conn, 1);
+    mark_task_cancelled(&conn, 2);
+    mark_task_done(&conn, 3);
+    mark_task_done(&conn, 4);
+
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET created_at = 0;
+        UPDATE tasks SET completed_at = {} WHERE id IN (1, 2);
+        UPDATE tasks SET completed_at = {cutoff} WHERE id = 3;
+        UPDATE tasks SET completed_at = NULL WHERE id = 4;",
+        cutoff - 1
+    ))
+    .unwrap();
+
+    assert_eq!(collect_garbage_older_than(&conn, cutoff).unwrap(), 3);
+    assert!(get_task_by_id(&conn, 3).unwrap().is_some());
+    assert!(get_task_by_id(&conn, 5).unwrap().is_some());
+    assert_eq!(count_tasks(&conn), 2);
+}
+
+#[test]
+fn test_delete_task() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+
+    assert_eq!(delete_task(&conn, 1).unwrap(), 1);
+    assert_eq!(count_tasks(&conn), 1);
+    // Soft deleted rows would still match, so this fails only once the row is gone
+    assert!(delete_task(&conn, 1).is_err());
+}
+
+#[test]
+fn test_purge_completed_older_than() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    mark_tasks_done(&conn, &[1, 2]);
+    mark_task_cancelled(&conn, 3);
+
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET completed_at = {} WHERE id IN (1, 3);
+        UPDATE tasks SET completed_at = {} WHERE id = 2;",
+        now - 40 * 86400,
+        now - 10 * 86400
+    ))
+    .unwrap();
+
+    assert_eq!(count_completed_older_than(&conn, Some(30)).unwrap(), 1);
+    assert_eq!(count_completed_older_than(&conn, Some(5)).unwrap(), 2);
+    assert_eq!(count_completed_older_than(&conn, None).unwrap(), 2);
+    assert_eq!(count_tasks(&conn), 4);
+
+    assert_eq!(purge_completed_older_than(&conn, Some(30)).unwrap(), 1);
+    assert_eq!(count_completed_older_than(&conn, None).unwrap(), 1);
+    assert!(get_task_by_id(&conn, 3).unwrap().is_some());
+}
+
+#[test]
+fn test_list_tasks_ndjson() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 5); // id 1
+    add_task!(&conn, "Test task \"quoted\""); // id 2
+    add_task!(&conn, "Test task", 1); // id 3
+
+    let mut out = Vec::new();
+    list_tasks_ndjson(&conn, &ListOptions::default(), &mut out);
+
+    let tasks: Vec<Task> = serde_json::Deserializer::from_slice(&out)
+        .into_iter::<Task>()
+        .collect::<result::Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+    assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 3]);
+}
+
+#[test]
+fn test_list_tasks_json_pretty() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 5);
+    add_task!(&conn, "Test task", 1);
+
+    let mut compact = Vec::new();
+    list_tasks_json(&conn, &ListOptions::default(), false, &mut compact);
+
+    let mut pretty = Vec::new();
+    list_tasks_json(&conn, &ListOptions::default(), true, &mut pretty);
+    let pretty = String::from_utf8(pretty).unwrap();
+
+    // serde_json indents with two spaces by default
+    assert_eq!(String::from_utf8(compact).unwrap().lines().count(), 1);
+    assert!(pretty.contains("[\n  {\n    \"id\": 1,"));
+    assert_eq!(serde_json::from_str::<Vec<Task>>(&pretty).unwrap().len(), 2);
+}
+
+#[test]
+fn test_list_created_between() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    conn.execute_batch("UPDATE tasks SET created_at = id * 100;")
+        .unwrap();
+
+    let since = ListOptions::default().created_after(Some(200));
+    let until = ListOptions::default().created_until(Some(200));
+
+    let ids = |opts: &ListOptions| -> Vec<i64> {
+        query_tasks(&conn, opts)
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect()
+    };
+
+    assert_eq!(ids(&since), [2, 3]);
+    assert_eq!(ids(&until), [1, 2]);
+    assert_eq!(ids(&since.created_until(Some(200))), [2]);
+}
+
+#[test]
+fn test_list_age_days() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+    let day = 86400;
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    // id 4 is a minute ahead, so it stays within `max_age_days(0)` however
+    // long the test takes
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET created_at = {} WHERE id = 1;
+        UPDATE tasks SET created_at = {} WHERE id = 2;
+        UPDATE tasks SET created_at = {} WHERE id = 3;
+        UPDATE tasks SET created_at = {} WHERE id = 4;",
+        now - 10 * day,
+        now - 7 * day - 60,
+        now - 7 * day + 60,
+        now + 60,
+    ))
+    .unwrap();
+
+    let ids = |opts: ListOptions| -> Vec<i64> {
+        query_tasks(&conn, &opts.filter(ListFilter::All))
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect()
+    };
+
+    assert_eq!(ids(ListOptions::default().min_age_days(Some(7))), [1, 2]);
+    assert_eq!(ids(ListOptions::default().max_age_days(Some(7))), [3, 4]);
+    assert_eq!(ids(ListOptions::default().max_age_days(Some(0))), [4]);
+    assert_eq!(
+        ids(ListOptions::default()
+            .min_age_days(Some(5))
+            .max_age_days(Some(8))),
+        [2, 3]
+    );
+    assert_eq!(
+        ids(ListOptions::default()
+            .min_age_days(Some(5))
+            .created_after(Some(now - 8 * day))),
+        [2, 3]
+    );
+}
+
+#[test]
+fn test_list_due_between() {
+    let conn = init_test_db();
+    let due_in = |days: u64| {
+        (chrono::Local::now().date_naive() + chrono::Days::new(days))
+            .format("%d.%m.%Y")
+            .to_string()
+    };
+
+    add_task!(&conn, "Test task", 3, due_in(0)); // id 1
+    add_task!(&conn, "Test task", 3, due_in(6)); // id 2
+    add_task!(&conn, "Test task", 3, due_in(7)); // id 3
+    add_task!(&conn, "Test task", 3, due_in(13)); // id 4
+    add_task!(&conn, "Test task", 3, due_in(14)); // id 5
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string()); // id 6
+    add_task!(&conn, "Test task"); // id 7
+
+    let week = |from, to| -> Vec<i64> {
+        let range = (
+            crate::date::day_bounds(from).unwrap().0,
+            crate::date::day_bounds(to).unwrap().0,
+        );
+        let opts = ListOptions::default()
+            .filter(ListFilter::All)
+            .due_between(Some(range));
+        query_tasks(&conn, &opts)
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect()
+    };
+
+    assert_eq!(week(0, 7), [1, 2]);
+    assert_eq!(week(7, 14), [3, 4]);
+}
+
+#[test]
+fn test_list_stale_tasks() {
+    let conn = init_test_db();
+    let now = Utc::now().timestamp();
+    let day = 86400;
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    conn.execute_batch(&format!(
+        "UPDATE tasks SET created_at = {} WHERE id IN (1, 4);
+        UPDATE tasks SET created_at = {} WHERE id = 2;",
+        now - 7 * day,
+        now - 6 * day,
+    ))
+    .unwrap();
+    select_next_task(&conn, Some(4));
+
+    let stale: Vec<i64> = list_stale_tasks(&conn, 7)
+        .unwrap()
+        .iter()
+        .map(|t| t.id)
+        .collect();
+
+    assert_eq!(stale, [1]);
+    assert_eq!(list_stale_tasks(&conn, 6).unwrap().len(), 2);
+}
+
+#[test]
+fn test_parse_priority() {
+    assert_eq!(parse_priority("!"), Ok(4));
+    assert_eq!(parse_priority("."), Ok(1));
+    assert_eq!(parse_priority("!!!"), Ok(5));
+    assert_eq!(parse_priority("2"), Ok(2));
+    assert!(parse_priority("!!").is_err());
+    assert!(parse_priority("6").is_err());
+
+    for priority in 1..=5 {
+        assert_eq!(
+            priority_symbol_to_int(priority_symbol(priority)),
+            Some(priority)
+        );
+    }
+}
+
+#[test]
+fn test_list_due_null() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 3, "31.12.2030".to_string()); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task", 3, "01.01.2031".to_string()); // id 3
+    add_task!(&conn, "Test task"); // id 4
+
+    let opts = ListOptions::default()
+        .filter(ListFilter::All)
+        .due_null(true);
+    let ids: Vec<i64> = query_tasks(&conn, &opts)
+        .unwrap()
+        .iter()
+        .map(|t| t.id)
+        .collect();
+
+    assert_eq!(ids, [2, 4]);
+}
+
+#[test]
+fn test_list_notes_filter() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    add_task!(&conn, "Test task"); // id 3
+    append_task_notes(&conn, 2, "Some context").unwrap();
+    conn.execute("UPDATE tasks SET notes = '' WHERE id = 3", [])
+        .unwrap();
+
+    let ids = |notes_filter| -> Vec<i64> {
+        let opts = ListOptions::default().notes_filter(Some(notes_filter));
+        query_tasks(&conn, &opts)
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect()
+    };
+
+    assert_eq!(ids(NotesFilter::HasNotes), [2]);
+    assert_eq!(ids(NotesFilter::NoNotes), [1, 3]);
+}
+
+#[test]
+fn test_list_id_range() {
+    let conn = init_test_db();
+
+    for _ in 0..6 {
+        add_task!(&conn, "Test task");
+    }
+
+    let opts = ListOptions::default().id_range(Some((2, 4)));
+    let ids: Vec<i64> = query_tasks(&conn, &opts)
+        .unwrap()
+        .iter()
+        .map(|t| t.id)
+        .collect();
+
+    assert_eq!(ids, [2, 3, 4]);
+}
+
+#[test]
+fn test_add_task_returns_id() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    let id = add_task(&conn, "Another task", Some(5), None, None, None, None).unwrap();
+
+    let task = get_task_by_id(&conn, id).unwrap().unwrap();
+
+    assert_eq!(id, 2);
+    assert_eq!(task.task, "Another task");
+}
+
+#[test]
+fn test_format_task() {
     let conn = init_test_db();
 
-    add_task!(&conn, "Test task");
-    select_next_task(&conn, None);
+    add_task(
+        &conn,
+        "Test task",
+        Some(4),
+        None,
+        Some("work".to_string()),
+        None,
+        None,
+    )
+    .unwrap();
+    let task = get_single_task(&conn);
+
+    let table = format_task(&task, DisplayFormat::Table);
+    let compact = format_task(&task, DisplayFormat::Compact);
+    let minimal = format_task(&task, DisplayFormat::Minimal);
+
+    assert!(table.starts_with("1    Pending     [ ! ]  "));
+    assert!(compact.starts_with("1|Pending|!|"));
+    assert!(compact.ends_with("|Never|Test task|work"));
+    assert_eq!(minimal, "1 Test task");
+}
+
+#[test]
+fn test_format_compact() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        Some(4),
+        Some("1.1.2030".to_string()),
+        Some("work,home".to_string()),
+        None,
+        None,
+    )
+    .unwrap();
+    add_task!(&conn, "Another task");
 
+    let dated = format_compact(&get_task_by_id(&conn, 1).unwrap().unwrap());
+    let undated = format_compact(&get_task_by_id(&conn, 2).unwrap().unwrap());
+
+    assert_eq!(
+        dated,
+        "1 Pending Test task #work #home (P:4 DUE:2030-01-01)"
+    );
+    assert_eq!(undated, "2 Pending Another task (P:3)");
+    for line in [dated, undated] {
+        assert!(!line.contains("  "));
+        assert_eq!(line.trim_end(), line);
+    }
+}
+
+#[test]
+fn test_task_field() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 4);
     let task = get_single_task(&conn);
 
-    assert_eq!(task.status, Status::InProgress)
+    assert_eq!(task.field(Column::Id), "1");
+    assert_eq!(task.field(Column::Status), "Pending");
+    assert_eq!(task.field(Column::Priority), "4");
+    assert_eq!(task.field(Column::Due), "Never");
+    assert_eq!(task.field(Column::Task), "Test task");
 }
 
 #[test]
-fn test_select_next_task_from_multiple() {
+fn test_task_to_show() {
     let conn = init_test_db();
 
-    add_task!(&conn, "Test task", 1); // id 1
+    add_task!(&conn, "Test task"); // id 1
     add_task!(&conn, "Test task"); // id 2
-    add_task!(&conn, "Test task", 5); // id 3
-    add_task!(&conn, "Test task", 4); // id 4
+    select_next_task(&conn, Some(1));
+
+    assert_eq!(task_to_show(&conn, None).unwrap().unwrap().id, 1);
+    assert_eq!(task_to_show(&conn, Some(2)).unwrap().unwrap().id, 2);
+    assert!(task_to_show(&conn, Some(42)).is_err());
+}
+
+#[test]
+fn test_select_next_task_tie_breaks() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 3, "1.1.2030".to_string()); // id 1
+    add_task!(&conn, "Test task", 3, "1.1.2030".to_string()); // id 2
+    add_task!(&conn, "Test task", 3, "1.1.2029".to_string()); // id 3
+    add_task!(&conn, "Test task", 4, "1.1.2031".to_string()); // id 4
+
+    // Same priority and due date, the older task goes first even with a higher id
+    conn.execute_batch(
+        "UPDATE tasks SET created_at = 200 WHERE id = 1;
+        UPDATE tasks SET created_at = 100 WHERE id IN (2, 3);
+        UPDATE tasks SET created_at = 300 WHERE id = 4;",
+    )
+    .unwrap();
+
+    let ids = next_batch(&conn, 4).unwrap();
+
+    // Priority first, then the closer due date, then creation time
+    assert_eq!(ids, [4, 3, 2, 1]);
+}
+
+#[test]
+fn test_get_task_by_id() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task"); // id 1
+    add_task!(&conn, "Test task"); // id 2
+    mark_task_cancelled(&conn, 2);
+    collect_garbage(&conn);
+
+    assert_eq!(get_task_by_id(&conn, 1).unwrap().unwrap().id, 1);
+    assert!(get_task_by_id(&conn, 2).unwrap().is_none());
+    assert!(get_task_by_id(&conn, 42).unwrap().is_none());
+}
+
+#[test]
+fn test_task_display_color() {
+    let conn = init_test_db();
 
+    add_task!(&conn, "Test task");
     select_next_task(&conn, None);
 
-    let task = conn
-        .query_row("SELECT * FROM tasks WHERE status = 1;", [], |row| {
-            Task::try_from(row)
-        })
-        .unwrap();
+    // Only the status is painted, and only when color is enabled. Nothing in
+    // the tests changes whether it is, so this does not depend on test order.
+    let task = get_single_task(&conn);
+    let colored = crate::output::color_enabled();
 
-    assert_eq!(task.status, Status::InProgress);
-    assert_eq!(task.id, 3);
+    assert_eq!(task.to_string().contains("\x1b["), colored);
 }
 
 #[test]
-fn test_no_next_task_to_select() {
+fn test_truncate_task_text() {
+    assert_eq!(truncate_task_text("Short task", 20), "Short task");
+    assert_eq!(truncate_task_text("Exactly ten", 11), "Exactly ten");
+    assert_eq!(truncate_task_text("A much longer task", 10), "A much lo…");
+    assert_eq!(truncate_task_text("Äöäöäöäö", 4), "Äöä…");
+    assert_eq!(truncate_task_text("Any task", 0), "");
+
+    assert!(matches!(truncate_task_text("Short", 10), Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_list_with_width() {
+    let conn = init_test_db();
+
+    add_task!(
+        &conn,
+        "A task with a description far too long for a narrow terminal"
+    );
+
+    let mut out = Vec::new();
+    list_tasks(&conn, &ListOptions::default().width(Some(70)), &mut out);
+
+    let out = String::from_utf8(out).unwrap();
+    let row = out.lines().last().unwrap();
+
+    assert_eq!(row.chars().count(), 70);
+    assert!(row.ends_with("\"A task with a descr…\""));
+}
+
+#[test]
+fn test_pause_all_active() {
     let conn = init_test_db();
 
     add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    next_batch(&conn, 3).unwrap();
     mark_task_done(&conn, 1);
-    select_next_task(&conn, None);
 
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM tasks WHERE status = 1;", [], |row| {
-            row.get(0)
-        })
+    assert_eq!(pause_all_active(&conn).unwrap(), 2);
+    assert_eq!(pause_all_active(&conn).unwrap(), 0);
+
+    let opts = ListOptions::default().filter(ListFilter::All);
+    let statuses: Vec<_> = query_tasks(&conn, &opts)
+        .unwrap()
+        .into_iter()
+        .map(|task| task.status)
+        .collect();
+
+    assert_eq!(
+        statuses,
+        [
+            Status::Completed,
+            Status::Pending,
+            Status::Pending,
+            Status::Pending
+        ]
+    );
+}
+
+#[test]
+fn test_compute_eta() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    add_task(&conn, "Test task", None, None, None, None, Some(90)).unwrap();
+
+    let without = get_task_by_id(&conn, 1).unwrap().unwrap();
+    let with = get_task_by_id(&conn, 2).unwrap().unwrap();
+
+    assert_eq!(compute_eta(&without), None);
+    assert_eq!(compute_eta(&with), Some(with.created_at + 90 * 60));
+
+    let eta = with.created_at + 90 * 60;
+    assert_eq!(count_past_eta(&conn, eta).unwrap(), 0);
+    assert_eq!(count_past_eta(&conn, eta + 1).unwrap(), 1);
+}
+
+#[test]
+fn test_invalid_status_is_an_error() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    conn.execute("UPDATE tasks SET status = 99", []).unwrap();
+
+    assert!(matches!(
+        get_task_by_id(&conn, 1),
+        Err(rusqlite::Error::FromSqlConversionFailure(..))
+    ));
+    assert!(Status::try_from(99).is_err());
+}
+
+#[test]
+fn test_status_round_trip() {
+    for status in [
+        Status::Pending,
+        Status::InProgress,
+        Status::Completed,
+        Status::Cancelled,
+        Status::Blocked,
+    ] {
+        assert_eq!(status.to_string().parse::<Status>().unwrap(), status);
+    }
+
+    assert_eq!(Status::InProgress.to_string(), "In Progress");
+    assert_eq!("in_progress".parse::<Status>().unwrap(), Status::InProgress);
+    assert!("someday".parse::<Status>().is_err());
+}
+
+#[test]
+fn test_count_by_status() {
+    let conn = init_test_db();
+
+    for _ in 0..6 {
+        add_task!(&conn, "Test task");
+    }
+    next_batch(&conn, 3).unwrap(); // ids 1-3
+    mark_task_done(&conn, 1);
+    mark_task_done(&conn, 2);
+    mark_task_cancelled(&conn, 4);
+
+    let counts = count_by_status(&conn).unwrap();
+
+    assert_eq!(counts[&Status::Pending], 2);
+    assert_eq!(counts[&Status::InProgress], 1);
+    assert_eq!(counts[&Status::Completed], 2);
+    assert_eq!(counts[&Status::Cancelled], 1);
+    assert_eq!(counts.get(&Status::Blocked), None);
+}
+
+#[test]
+fn test_summary_counts() {
+    let conn = init_test_db();
+
+    for _ in 0..4 {
+        add_task!(&conn, "Test task"); // ids 1-4
+    }
+    add_task!(&conn, "Test task", 3, "1.1.2020".to_string()); // id 5
+    add_task!(&conn, "Test task", 3, "2.1.2020".to_string()); // id 6
+    add_task!(&conn, "Test task", 3, "1.1.2999".to_string()); // id 7
+    for _ in 0..3 {
+        add_task!(&conn, "Test task"); // ids 8-10
+    }
+    select_next_task(&conn, Some(5));
+    mark_tasks_done(&conn, &[1, 2]);
+    mark_task_cancelled(&conn, 3);
+    assign_task(&conn, 6, "alice").unwrap();
+    assign_task(&conn, 8, "alice").unwrap();
+
+    let today = start_of_today().unwrap();
+    let opts = ListOptions::default();
+    let counts = count_by_status_matching(&conn, &opts).unwrap();
+
+    assert_eq!(counts[&Status::Pending], 6);
+    assert_eq!(counts[&Status::InProgress], 1);
+    assert_eq!(count_overdue(&conn, &opts, today).unwrap(), 2);
+
+    let mine = ListOptions::default().assignee(Some("alice".to_string()));
+    let counts = count_by_status_matching(&conn, &mine).unwrap();
+
+    assert_eq!(counts[&Status::Pending], 2);
+    assert_eq!(counts.get(&Status::InProgress), None);
+    assert_eq!(count_overdue(&conn, &mine, today).unwrap(), 1);
+}
+
+#[test]
+fn test_progress_empty() {
+    let conn = init_test_db();
+
+    let report = compute_progress(&conn).unwrap();
+
+    assert_eq!((report.total, report.completed), (0, 0));
+    assert!(report.by_tag.is_empty());
+    assert_eq!(percent(report.completed, report.total), 0);
+}
+
+#[test]
+fn test_progress_by_tag() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".into()),
+        None,
+        None,
+    )
+    .unwrap(); // id 1
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work,home".into()),
+        None,
+        None,
+    )
+    .unwrap(); // id 2
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("home".into()),
+        None,
+        None,
+    )
+    .unwrap(); // id 3
+    add_task!(&conn, "Test task"); // id 4
+    add_task(
+        &conn,
+        "Test task",
+        None,
+        None,
+        Some("work".into()),
+        None,
+        None,
+    )
+    .unwrap(); // id 5
+    mark_tasks_done(&conn, &[1, 2, 4]);
+    mark_task_cancelled(&conn, 5);
+
+    let report = compute_progress(&conn).unwrap();
+
+    assert_eq!((report.total, report.completed), (4, 3));
+    assert_eq!(
+        report.by_tag,
+        [("home".to_string(), 1, 2), ("work".to_string(), 2, 2)]
+    );
+    assert_eq!(percent(1, 3), 33);
+}
+
+#[test]
+fn test_repeat_task() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Deploy",
+        Some(4),
+        None,
+        Some("ops".into()),
+        None,
+        Some(30),
+    )
+    .unwrap(); // id 1
+    mark_task_done(&conn, 1);
+
+    let id = repeat_task(&conn, 1).unwrap();
+    let original = get_task_by_id(&conn, 1).unwrap().unwrap();
+    let repeated = get_task_by_id(&conn, id).unwrap().unwrap();
+
+    assert_eq!(id, 2);
+    assert_eq!(original.status, Status::Completed);
+    assert_eq!(repeated.status, Status::InProgress);
+    assert_eq!(
+        (
+            repeated.task.as_str(),
+            repeated.priority,
+            repeated.tags.as_deref()
+        ),
+        ("Deploy", 4, Some("ops"))
+    );
+    assert_eq!(repeated.estimate_minutes, Some(30));
+    assert!(repeat_task(&conn, 42).is_err());
+}
+
+#[test]
+fn test_add_from_template() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Write report",
+        Some(4),
+        None,
+        Some("work".into()),
+        None,
+        Some(60),
+    )
+    .unwrap(); // id 1
+    mark_task_done(&conn, 1);
+
+    let copy = add_from_template(&conn, 1, AddOverrides::default()).unwrap();
+    let copy = get_task_by_id(&conn, copy).unwrap().unwrap();
+    assert_eq!(copy.status, Status::Pending);
+    assert_eq!(
+        (copy.task.as_str(), copy.priority, copy.tags.as_deref()),
+        ("Write report", 4, Some("work"))
+    );
+    assert_eq!(copy.estimate_minutes, Some(60));
+
+    let overrides = AddOverrides {
+        task: Some("Write summary".into()),
+        priority: Some(2),
+        tags: Some("home".into()),
+        ..Default::default()
+    };
+    let changed = add_from_template(&conn, 1, overrides).unwrap();
+    let changed = get_task_by_id(&conn, changed).unwrap().unwrap();
+    assert_eq!(
+        (
+            changed.task.as_str(),
+            changed.priority,
+            changed.tags.as_deref()
+        ),
+        ("Write summary", 2, Some("home"))
+    );
+    assert_eq!(changed.estimate_minutes, Some(60));
+
+    assert!(add_from_template(&conn, 42, AddOverrides::default()).is_err());
+}
+
+#[test]
+fn test_bulk_mark_done() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    add_task!(&conn, "Test task");
+    mark_task_done(&conn, 2);
+
+    let (done, failed) = bulk_mark_done(&conn, &[1, 2, 3, 42]);
+    let failed: Vec<i64> = failed.into_iter().map(|(id, _)| id).collect();
+
+    assert_eq!(done, [1, 3]);
+    assert_eq!(failed, [2, 42]);
+    assert_eq!(count_by_status(&conn).unwrap()[&Status::Completed], 3);
+}
+
+#[test]
+fn test_task_to_insert_sql() {
+    let conn = init_test_db();
+
+    add_task(
+        &conn,
+        "Test task with 'quotes'",
+        Some(4),
+        Some("01.03.2026".to_string()),
+        Some("work".to_string()),
+        None,
+        Some(30),
+    )
+    .unwrap();
+    add_task!(&conn, "Test task");
+    select_next_task(&conn, Some(1));
+    append_task_notes(&conn, 1, "It's a note").unwrap();
+    mark_task_done(&conn, 2);
+    conn.execute("UPDATE tasks SET depends_on = 1 WHERE id = 2;", [])
         .unwrap();
 
-    assert_eq!(count, 0)
+    let tasks = query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+    let sql: Vec<String> = tasks.iter().map(task_to_insert_sql).collect();
+
+    let fresh = init_test_db();
+    fresh.execute_batch(&sql.join("\n")).unwrap();
+
+    // Every column, including ones `Task` leaves out of its output
+    let rows = |conn: &Connection| -> Vec<Vec<rusqlite::types::Value>> {
+        let mut statement = conn.prepare("SELECT * FROM tasks ORDER BY id;").unwrap();
+        let columns = statement.column_count();
+        statement
+            .query_map([], |row| (0..columns).map(|i| row.get(i)).collect())
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap()
+    };
+    assert_eq!(rows(&fresh), rows(&conn));
 }
 
 #[test]
-fn test_cancel_and_gc() {
+fn test_append_task_notes() {
     let conn = init_test_db();
 
     add_task!(&conn, "Test task");
+    append_task_notes(&conn, 1, "First").unwrap();
+    append_task_notes(&conn, 1, "Checked with Alice").unwrap();
 
-    mark_task_cancelled(&conn, 1);
-    collect_garbage(&conn);
+    let task = get_single_task(&conn);
+    assert_eq!(task.notes.as_deref(), Some("First\nChecked with Alice"));
+    assert!(append_task_notes(&conn, 2, "Missing").is_err());
+}
 
-    assert_eq!(count_tasks(&conn), 0)
+#[test]
+fn test_tasks_by_status() {
+    let conn = init_test_db();
+
+    for _ in 0..5 {
+        add_task!(&conn, "Test task");
+    }
+    mark_task_blocked(&conn, 1);
+    mark_task_done(&conn, 2);
+    select_next_task(&conn, Some(3));
+
+    let groups = tasks_by_status(&conn).unwrap();
+    let statuses: Vec<Status> = groups.keys().copied().collect();
+
+    assert_eq!(
+        statuses,
+        [
+            Status::Pending,
+            Status::InProgress,
+            Status::Completed,
+            Status::Blocked
+        ]
+    );
+    assert_eq!(groups[&Status::Pending].len(), 2);
+
+    let mut out = Vec::new();
+    list_tasks_by_status(&conn, true, &mut out);
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.starts_with("## Pending (2)\n"));
+    assert!(out.contains("\n## Cancelled (0)\n\n## Blocked (1)\n"));
+}
+
+#[test]
+fn test_list_from_id_pages() {
+    let conn = init_test_db();
+
+    let tx = conn.unchecked_transaction().unwrap();
+    for _ in 0..1000 {
+        add_task!(&tx, "Test task");
+    }
+    tx.commit().unwrap();
+
+    let mut seen = vec![];
+    let mut cursor = None;
+
+    loop {
+        let opts = ListOptions::default()
+            .filter(ListFilter::All)
+            .after_id(cursor)
+            .limit(Some(300));
+        let page = query_tasks(&conn, &opts).unwrap();
+
+        match page.last() {
+            Some(last) => cursor = Some(last.id),
+            None => break,
+        }
+        seen.extend(page.iter().map(|task| task.id));
+    }
+
+    assert_eq!(seen, (1..=1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_score() {
+    let day = 86400;
+    let now = 100 * day;
+
+    // Created just now, not due
+    assert_eq!(score(3, None, now, now), 30.0);
+    // Four days old, due in the future
+    assert_eq!(score(5, Some(now + day), now - 4 * day, now), 46.0);
+    // Ten days old, two days overdue
+    assert_eq!(score(1, Some(now - 2 * day), now - 10 * day, now), 10.0);
+    // Half a day
+    assert_eq!(score(2, None, now - day / 2, now), 19.5);
+}
+
+#[test]
+fn test_compute_scores_order() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 2); // id 1
+    add_task!(&conn, "Test task", 4); // id 2
+    add_task!(&conn, "Test task", 3, "01.01.2000".to_string()); // id 3
+    add_task!(&conn, "Test task", 5); // id 4
+    select_next_task(&conn, Some(4));
+
+    let ids: Vec<i64> = compute_scores(&conn)
+        .unwrap()
+        .iter()
+        .map(|(task, _)| task.id)
+        .collect();
+
+    assert_eq!(ids, [3, 2, 1]);
+}
+
+#[test]
+fn test_list_created_before_and_after_boundaries() {
+    use crate::date::{parse_input_date_after, parse_input_date_before, parse_input_date_end};
+
+    let conn = init_test_db();
+    let start = parse_input_date("01.03.2026").unwrap();
+    let end = parse_input_date_end("01.03.2026").unwrap();
+
+    // Created a second before, at the start, at the end and a second after the day
+    for created_at in [start - 1, start, end, end + 1] {
+        add_task!(&conn, "Test task");
+        conn.execute(
+            "UPDATE tasks SET created_at = ?1 WHERE id = last_insert_rowid()",
+            [created_at],
+        )
+        .unwrap();
+    }
+
+    let ids = |opts: ListOptions| -> Vec<i64> {
+        query_tasks(&conn, &opts.filter(ListFilter::All))
+            .unwrap()
+            .iter()
+            .map(|task| task.id)
+            .collect()
+    };
+
+    let before = parse_input_date_before("01.03.2026").unwrap();
+    let after = parse_input_date_after("01.03.2026").unwrap();
+
+    // Tasks created on the day itself are on neither side of it
+    assert_eq!(ids(ListOptions::default().created_until(Some(before))), [1]);
+    assert_eq!(ids(ListOptions::default().created_after(Some(after))), [4]);
+}
+
+#[test]
+fn test_age_column() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task");
+    conn.execute(
+        "UPDATE tasks SET created_at = ?1",
+        [Utc::now().timestamp() - 12 * 86400 - 60],
+    )
+    .unwrap();
+    let task = get_single_task(&conn);
+    let columns = [Column::Id, Column::Age, Column::Task];
+
+    assert_eq!(task_header(&columns), "ID     AGE TASK");
+    assert_eq!(task.row(&columns, None), "1      12d \"Test task\"");
+    assert_eq!(task.field(Column::Age), "12");
+}
+
+#[test]
+fn test_header_matches_row_columns() {
+    let conn = init_test_db();
+
+    add_task!(&conn, "Test task", 5, "01.03.2026".to_string()); // id 1
+    add_task!(&conn, "Test task", 1); // id 2
+    mark_task_done(&conn, 1);
+    archive_tasks(&conn, false).unwrap();
+
+    let task = get_task_by_id(&conn, 2).unwrap().unwrap();
+    let archived = list_archived(&conn).unwrap().remove(0);
+
+    for (columns, task) in [(DEFAULT_COLUMNS, &task), (ARCHIVE_COLUMNS, &archived)] {
+        let mut header = Vec::new();
+        write_task_header(&mut header, columns).unwrap();
+        let header = String::from_utf8(header).unwrap();
+        let row = task.row(columns, None);
+
+        let headers: Vec<&str> = header.split_whitespace().collect();
+        let expected: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+        assert_eq!(headers, expected);
+
+        // Each column starts where its header does
+        for column in columns {
+            let start = header.find(column.header()).unwrap();
+            assert!(start == 0 || row[..start].ends_with(' '));
+            assert!(!row[start..].starts_with(' '));
+        }
+        assert_eq!(header.find("TASK"), row.find('"'));
+    }
 }
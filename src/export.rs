@@ -0,0 +1,391 @@
+use crate::date::{parse_iso_date, timestamp_to_local_str, timestamp_to_local_weekday_str};
+use crate::db::{self, ListFilter, ListOptions, Status, Task};
+use anyhow::Error;
+use chrono::Utc;
+use clap::ValueEnum;
+use rusqlite::Connection;
+use serde_json::json;
+use std::fs;
+use std::io::Write;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::SplitWhitespace;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// One line per task in the todo.txt format, see http://todotxt.org
+    Todotxt,
+    /// org-mode headings, one per task, see https://orgmode.org
+    Org,
+}
+
+/// `(A) 2025-09-01 Task text @tag due:2025-09-15`, where priorities 5 to 1
+/// map to (A) to (E). Completed tasks start with `x` and their completion date
+/// and keep the priority as `pri:A`, cancelled tasks are closed the same way
+/// and marked `status:cancelled`.
+pub fn task_to_todotxt(task: &Task) -> String {
+    let closed = matches!(task.status, Status::Completed | Status::Cancelled);
+    let mut line = String::new();
+
+    if closed {
+        // Tasks completed before `completed_at` was tracked fall back to their creation time
+        let completed = task.completed_at.unwrap_or(task.created_at);
+        line += "x ";
+        if let Ok(completed) = timestamp_to_local_str(completed) {
+            line += &format!("{completed} ");
+        }
+    } else if let Some(letter) = priority_letter(task.priority) {
+        line += &format!("({letter}) ");
+    }
+    if let Ok(created) = timestamp_to_local_str(task.created_at) {
+        line += &format!("{created} ");
+    }
+    line += &task.task;
+
+    for tag in task.tag_list() {
+        line += &format!(" @{tag}");
+    }
+    if let Some(due) = task.due_at.and_then(|due| timestamp_to_local_str(due).ok()) {
+        line += &format!(" due:{due}");
+    }
+    if let Some(letter) = priority_letter(task.priority).filter(|_| closed) {
+        line += &format!(" pri:{letter}");
+    }
+    if task.status == Status::Cancelled {
+        line += " status:cancelled";
+    }
+
+    line
+}
+
+/// The inverse of `task_to_todotxt`, `None` for blank lines. Dates that don't
+/// parse are kept as part of the task text.
+pub fn task_from_todotxt(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = Task {
+        priority: 3,
+        ..Task::default()
+    };
+
+    if words.next_if_eq(&"x").is_some() {
+        task.status = Status::Completed;
+        task.completed_at = next_date(&mut words);
+    } else if let Some(priority) = words.peek().and_then(|word| priority_from_prefix(word)) {
+        task.priority = priority;
+        words.next();
+    }
+    task.created_at = next_date(&mut words).unwrap_or_else(|| Utc::now().timestamp());
+
+    let mut text = Vec::new();
+    let mut tags = Vec::new();
+
+    for word in words {
+        if let Some(due) = word
+            .strip_prefix("due:")
+            .and_then(|due| parse_iso_date(due).ok())
+        {
+            task.due_at = Some(due);
+        } else if let Some(priority) = word.strip_prefix("pri:").and_then(letter_priority) {
+            task.priority = priority;
+        } else if word == "status:cancelled" {
+            task.status = Status::Cancelled;
+        } else if let Some(tag) = word.strip_prefix('@').filter(|tag| !tag.is_empty()) {
+            tags.push(tag);
+        } else {
+            text.push(word);
+        }
+    }
+    if text.is_empty() {
+        return None;
+    }
+
+    task.task = text.join(" ");
+    task.tags = (!tags.is_empty()).then(|| tags.join(","));
+    Some(task)
+}
+
+/// A `* TODO` heading, `* DONE` for completed and `* CANCELLED` for cancelled
+/// tasks, with the due date as SCHEDULED when set and the priority mapped 5 to
+/// 1 onto [#A] to [#E].
+pub fn format_org_entry(task: &Task) -> String {
+    let keyword = match task.status {
+        Status::Completed => "DONE",
+        Status::Cancelled => "CANCELLED",
+        _ => "TODO",
+    };
+    let mut entry = format!("* {keyword} {}", task.task);
+
+    if let Some(due) = task
+        .due_at
+        .and_then(|due| timestamp_to_local_weekday_str(due).ok())
+    {
+        entry += &format!("\n  SCHEDULED: <{due}>");
+    }
+    if let Some(letter) = priority_letter(task.priority) {
+        entry += &format!("\n  PRIORITY: [#{letter}]");
+    }
+
+    entry
+}
+
+fn priority_letter(priority: i64) -> Option<char> {
+    match priority {
+        1..=5 => Some((b'A' + (5 - priority) as u8) as char),
+        _ => None,
+    }
+}
+
+fn next_date(words: &mut Peekable<SplitWhitespace>) -> Option<i64> {
+    let date = parse_iso_date(words.peek()?).ok()?;
+    words.next();
+    Some(date)
+}
+
+fn letter_priority(letter: &str) -> Option<i64> {
+    match letter.as_bytes() {
+        [letter @ b'A'..=b'E'] => Some(5 - i64::from(letter - b'A')),
+        _ => None,
+    }
+}
+
+/// `(A)` to `(E)` at the start of an open task.
+fn priority_from_prefix(word: &str) -> Option<i64> {
+    word.strip_prefix('(')
+        .and_then(|word| word.strip_suffix(')'))
+        .and_then(letter_priority)
+}
+
+fn write_export(
+    conn: &Connection,
+    fmt: ExportFormat,
+    archived: bool,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let tasks = match archived {
+        true => db::list_archived(conn)?,
+        false => db::query_tasks(conn, &ListOptions::default().filter(ListFilter::All))?,
+    };
+
+    // CANCELLED is not one of org-mode's default keywords
+    if fmt == ExportFormat::Org {
+        writeln!(out, "#+TODO: TODO | DONE CANCELLED")?;
+    }
+    for task in tasks {
+        match fmt {
+            ExportFormat::Todotxt => writeln!(out, "{}", task_to_todotxt(&task))?,
+            ExportFormat::Org => writeln!(out, "{}", format_org_entry(&task))?,
+        }
+    }
+    Ok(())
+}
+
+/// Adds every task in a todo.txt file, returning the new ids.
+pub fn import_todotxt(conn: &Connection, input: &str) -> Result<Vec<i64>, Error> {
+    let mut ids = Vec::new();
+
+    for task in input.lines().filter_map(task_from_todotxt) {
+        ids.push(db::insert_task(conn, &task)?);
+    }
+    Ok(ids)
+}
+
+pub fn import_tasks(conn: &Connection, path: &Path) {
+    let imported = fs::read_to_string(path)
+        .map_err(Error::from)
+        .and_then(|input| import_todotxt(conn, &input));
+
+    match imported {
+        Ok(ids) => println!("Imported {} tasks.", ids.len()),
+        Err(err) => println!("{:?}", err),
+    }
+}
+
+pub fn export_tasks(conn: &Connection, fmt: ExportFormat, archived: bool, out: &mut dyn Write) {
+    if let Err(err) = write_export(conn, fmt, archived, out) {
+        println!("{:?}", err)
+    }
+}
+
+/// JSON Schema (draft-07) of a task as printed by `list --json` and `--ndjson`.
+pub fn task_json_schema() -> serde_json::Value {
+    let nullable_string = json!({ "type": ["string", "null"] });
+    let nullable_timestamp = json!({ "type": ["string", "null"], "format": "date-time" });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Task",
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer" },
+            "task": { "type": "string" },
+            "status": {
+                "type": "string",
+                "enum": ["pending", "in_progress", "completed", "cancelled", "blocked"]
+            },
+            "priority": { "type": "integer", "minimum": 1, "maximum": 5 },
+            "created_at": { "type": "string", "format": "date-time" },
+            "due_at": nullable_timestamp,
+            "notes": nullable_string,
+            "tags": nullable_string,
+            "archived_at": nullable_timestamp,
+            "assignee": nullable_string,
+            "estimate_minutes": { "type": ["integer", "null"] }
+        },
+        "required": ["id", "task", "status", "priority", "created_at"],
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn test_task_to_todotxt() {
+    let conn = db::init_test_db();
+
+    db::add_task(
+        &conn,
+        "Write report",
+        Some(5),
+        Some("15.9.2025".to_string()),
+        Some("work,urgent".to_string()),
+        None,
+        None,
+    )
+    .unwrap();
+    db::add_task(&conn, "Water plants", Some(1), None, None, None, None).unwrap();
+    db::add_task(&conn, "Call plumber", None, None, None, None, None).unwrap();
+    db::mark_task_done(&conn, 2);
+    db::mark_task_cancelled(&conn, 3);
+
+    let tasks = db::query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+    let created = timestamp_to_local_str(tasks[0].created_at).unwrap();
+
+    assert_eq!(
+        task_to_todotxt(&tasks[0]),
+        format!("(A) {created} Write report @work @urgent due:2025-09-15")
+    );
+    assert_eq!(
+        task_to_todotxt(&tasks[1]),
+        format!("x {created} {created} Water plants pri:E")
+    );
+    assert_eq!(
+        task_to_todotxt(&tasks[2]),
+        format!("x {created} {created} Call plumber pri:C status:cancelled")
+    );
+}
+
+#[test]
+fn test_todotxt_round_trip() {
+    let conn = db::init_test_db();
+    let input = "\
+(A) 2025-09-01 Write report @work @urgent due:2025-09-15
+(C) 2025-09-02 Water plants
+x 2025-09-05 2025-09-03 Call plumber pri:B
+x 2025-09-06 2025-09-04 Book flights @travel pri:C status:cancelled
+";
+
+    assert_eq!(import_todotxt(&conn, input).unwrap(), vec![1, 2, 3, 4]);
+
+    let mut out = Vec::new();
+    write_export(&conn, ExportFormat::Todotxt, false, &mut out).unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), input);
+
+    db::archive_tasks(&conn, true).unwrap();
+    let mut archived = Vec::new();
+    write_export(&conn, ExportFormat::Todotxt, true, &mut archived).unwrap();
+
+    assert_eq!(
+        String::from_utf8(archived).unwrap(),
+        input
+            .lines()
+            .skip(2)
+            .map(|line| format!("{line}\n"))
+            .collect::<String>()
+    );
+}
+
+#[test]
+fn test_task_from_todotxt() {
+    let task = task_from_todotxt("(B) 2025-09-01 Buy milk 2025-09-02 @home due:soon").unwrap();
+
+    assert_eq!(task.priority, 4);
+    assert_eq!(task.task, "Buy milk 2025-09-02 due:soon");
+    assert_eq!(task.tags.as_deref(), Some("home"));
+    assert_eq!(task.due_at, None);
+
+    assert_eq!(task_from_todotxt("   "), None);
+    assert_eq!(task_from_todotxt("x 2025-09-01 @home"), None);
+}
+
+#[test]
+fn test_format_org_entry() {
+    let conn = db::init_test_db();
+
+    db::add_task(
+        &conn,
+        "Write report",
+        Some(4),
+        Some("2.9.2025".to_string()),
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    db::add_task(&conn, "Water plants", Some(5), None, None, None, None).unwrap();
+    db::add_task(&conn, "Call plumber", Some(3), None, None, None, None).unwrap();
+    db::mark_task_done(&conn, 2);
+    db::mark_task_cancelled(&conn, 3);
+
+    let tasks = db::query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+
+    assert_eq!(
+        format_org_entry(&tasks[0]),
+        "* TODO Write report\n  SCHEDULED: <2025-09-02 Tue>\n  PRIORITY: [#B]"
+    );
+    assert_eq!(
+        format_org_entry(&tasks[1]),
+        "* DONE Water plants\n  PRIORITY: [#A]"
+    );
+    assert_eq!(
+        format_org_entry(&tasks[2]),
+        "* CANCELLED Call plumber\n  PRIORITY: [#C]"
+    );
+}
+
+#[test]
+fn test_priority_letter() {
+    assert_eq!(priority_letter(5), Some('A'));
+    assert_eq!(priority_letter(3), Some('C'));
+    assert_eq!(priority_letter(1), Some('E'));
+    assert_eq!(priority_letter(9), None);
+}
+
+#[test]
+fn test_task_json_schema() {
+    let conn = db::init_test_db();
+
+    db::add_task(
+        &conn,
+        "Write report",
+        Some(4),
+        Some("2.9.2025".to_string()),
+        Some("work".to_string()),
+        Some("alice".to_string()),
+        Some(30),
+    )
+    .unwrap();
+    db::add_task(&conn, "Bare task", None, None, None, None, None).unwrap();
+    db::mark_task_done(&conn, 2);
+
+    let validator = jsonschema::draft7::new(&task_json_schema()).unwrap();
+    let tasks = db::query_tasks(&conn, &ListOptions::default().filter(ListFilter::All)).unwrap();
+    assert_eq!(tasks.len(), 2);
+
+    for task in &tasks {
+        let value = serde_json::to_value(task).unwrap();
+        assert!(validator.is_valid(&value), "{value}");
+    }
+
+    let mut invalid = serde_json::to_value(&tasks[0]).unwrap();
+    invalid["status"] = json!("done");
+    assert!(!validator.is_valid(&invalid));
+}
@@ -0,0 +1,213 @@
+use crate::db::{self, Status};
+use anyhow::Result;
+use rusqlite::Connection;
+
+/// Something that happens to a task, passed to every registered `Hook`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TdEvent {
+    /// A task with this text is about to be added, a failing hook stops it
+    AddingTask(String),
+    TaskAdded(i64),
+    TaskStarted(i64),
+    TaskPaused(i64),
+    TaskBlocked(i64),
+    TaskDone(i64),
+    TaskCancelled(i64),
+    TaskRenamed(i64),
+    NoteAdded(i64),
+    TaskAssigned(i64),
+    DueChanged(i64),
+    TaskDeleted(i64),
+}
+
+/// Custom code run on task events, for embedding `td` as a library.
+pub trait Hook {
+    fn on_event(&self, event: &TdEvent) -> Result<()>;
+}
+
+/// A task database along with the hooks to run on changes made through it.
+/// Every change to a single task has a method here, hooks don't see changes
+/// made through `conn` directly or by the `td` command line itself.
+pub struct TdContext {
+    conn: Connection,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl TdContext {
+    pub fn new(conn: Connection) -> Self {
+        TdContext {
+            conn,
+            hooks: vec![],
+        }
+    }
+
+    /// The database `td` itself uses, migrated if needed.
+    pub fn open() -> Self {
+        TdContext::new(db::init_db())
+    }
+
+    /// A fresh database that only lives as long as the context.
+    pub fn open_in_memory() -> Result<Self> {
+        Ok(TdContext::new(db::open_in_memory()?))
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Hooks run in the order they were registered.
+    pub fn register_hook(&mut self, hook: Box<dyn Hook>) {
+        self.hooks.push(hook)
+    }
+
+    /// Runs every hook on `event`, stopping at the first one that fails.
+    pub fn dispatch(&self, event: &TdEvent) -> Result<()> {
+        self.hooks.iter().try_for_each(|hook| hook.on_event(event))
+    }
+
+    pub fn add_task(&self, task: &str, priority: Option<i64>) -> Result<i64> {
+        self.dispatch(&TdEvent::AddingTask(task.to_string()))?;
+        let id = db::add_task(&self.conn, task, priority, None, None, None, None)?;
+        self.dispatch(&TdEvent::TaskAdded(id))?;
+        Ok(id)
+    }
+
+    pub fn start_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&self.conn, id, Status::InProgress)?;
+        self.dispatch(&TdEvent::TaskStarted(id))
+    }
+
+    pub fn pause_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&self.conn, id, Status::Pending)?;
+        self.dispatch(&TdEvent::TaskPaused(id))
+    }
+
+    pub fn block_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&self.conn, id, Status::Blocked)?;
+        self.dispatch(&TdEvent::TaskBlocked(id))
+    }
+
+    pub fn complete_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&self.conn, id, Status::Completed)?;
+        self.dispatch(&TdEvent::TaskDone(id))
+    }
+
+    pub fn cancel_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&self.conn, id, Status::Cancelled)?;
+        self.dispatch(&TdEvent::TaskCancelled(id))
+    }
+
+    pub fn rename_task(&self, id: i64, task: &str) -> Result<()> {
+        db::rename_task(&self.conn, id, task)?;
+        self.dispatch(&TdEvent::TaskRenamed(id))
+    }
+
+    /// Appends a line to the notes of the task.
+    pub fn add_note(&self, id: i64, note: &str) -> Result<()> {
+        db::append_task_notes(&self.conn, id, note)?;
+        self.dispatch(&TdEvent::NoteAdded(id))
+    }
+
+    pub fn assign_task(&self, id: i64, assignee: &str) -> Result<()> {
+        db::assign_task(&self.conn, id, assignee)?;
+        self.dispatch(&TdEvent::TaskAssigned(id))
+    }
+
+    /// `due_at` is a Unix timestamp.
+    pub fn set_due(&self, id: i64, due_at: i64) -> Result<()> {
+        db::update_task_due(&self.conn, id, due_at)?;
+        self.dispatch(&TdEvent::DueChanged(id))
+    }
+
+    /// Removes the task for good, without going through `td gc`.
+    pub fn delete_task(&self, id: i64) -> Result<()> {
+        db::delete_task(&self.conn, id)?;
+        self.dispatch(&TdEvent::TaskDeleted(id))
+    }
+}
+
+#[cfg(test)]
+use {anyhow::anyhow, std::cell::RefCell, std::rc::Rc};
+
+#[cfg(test)]
+struct Recorder(Rc<RefCell<Vec<TdEvent>>>);
+
+#[cfg(test)]
+impl Hook for Recorder {
+    fn on_event(&self, event: &TdEvent) -> Result<()> {
+        self.0.borrow_mut().push(event.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_hooks_record_events() {
+    let events = Rc::new(RefCell::new(vec![]));
+    let mut ctx = TdContext::open_in_memory().unwrap();
+    ctx.register_hook(Box::new(Recorder(events.clone())));
+
+    let first = ctx.add_task("First", None).unwrap();
+    let second = ctx.add_task("Second", Some(5)).unwrap();
+    ctx.start_task(first).unwrap();
+    ctx.pause_task(first).unwrap();
+    ctx.block_task(first).unwrap();
+    ctx.complete_task(first).unwrap();
+    ctx.rename_task(second, "Renamed").unwrap();
+    ctx.add_note(second, "Note").unwrap();
+    ctx.assign_task(second, "alice").unwrap();
+    ctx.set_due(second, 1_000_000).unwrap();
+    ctx.cancel_task(second).unwrap();
+    ctx.delete_task(second).unwrap();
+
+    // A failed change is not reported
+    assert!(ctx.complete_task(first).is_err());
+    assert!(ctx.rename_task(second, "Gone").is_err());
+
+    assert_eq!(
+        *events.borrow(),
+        [
+            TdEvent::AddingTask("First".to_string()),
+            TdEvent::TaskAdded(first),
+            TdEvent::AddingTask("Second".to_string()),
+            TdEvent::TaskAdded(second),
+            TdEvent::TaskStarted(first),
+            TdEvent::TaskPaused(first),
+            TdEvent::TaskBlocked(first),
+            TdEvent::TaskDone(first),
+            TdEvent::TaskRenamed(second),
+            TdEvent::NoteAdded(second),
+            TdEvent::TaskAssigned(second),
+            TdEvent::DueChanged(second),
+            TdEvent::TaskCancelled(second),
+            TdEvent::TaskDeleted(second),
+        ]
+    );
+}
+
+#[cfg(test)]
+struct Veto;
+
+#[cfg(test)]
+impl Hook for Veto {
+    fn on_event(&self, event: &TdEvent) -> Result<()> {
+        match event {
+            TdEvent::AddingTask(task) if task.is_empty() => Err(anyhow!("Empty task")),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_failing_pre_add_hook_stops_add() {
+    let mut ctx = TdContext::open_in_memory().unwrap();
+    ctx.register_hook(Box::new(Veto));
+
+    assert!(ctx.add_task("", None).is_err());
+    assert!(ctx.add_task("Task", None).is_ok());
+
+    let count: i64 = ctx
+        .conn()
+        .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 1);
+}
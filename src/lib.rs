@@ -1,3 +1,17 @@
+mod bench;
 pub mod cli;
+mod config;
 mod date;
 mod db;
+mod export;
+pub mod hooks;
+pub mod logging;
+mod output;
+mod parse;
+pub mod pool;
+mod prompt;
+mod suggest;
+mod tui;
+mod watch;
+
+pub use db::Status;
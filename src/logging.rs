@@ -0,0 +1,13 @@
+use tracing_subscriber::EnvFilter;
+
+/// Sends log events to stderr, filtered by `RUST_LOG` and showing warnings and errors by default.
+pub fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .without_time()
+        .with_target(false)
+        .init();
+}
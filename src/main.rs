@@ -1,3 +1,4 @@
 fn main() {
+    td::logging::init_tracing();
     td::cli::run();
 }
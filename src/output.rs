@@ -0,0 +1,90 @@
+use std::env;
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use terminal_size::Width;
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turns color off for the rest of the process, e.g. for `--no-color`.
+pub fn disable_color() {
+    NO_COLOR.store(true, Ordering::Relaxed)
+}
+
+/// Color is used only when writing to a terminal and not opted out of,
+/// either with `--no-color` or a non-empty `NO_COLOR` (https://no-color.org).
+pub fn color_enabled() -> bool {
+    color_enabled_for(
+        NO_COLOR.load(Ordering::Relaxed),
+        env::var_os("NO_COLOR").as_deref(),
+        io::stdout().is_terminal(),
+    )
+}
+
+/// `color_enabled` given `--no-color`, the value of `NO_COLOR` and whether stdout is a terminal.
+fn color_enabled_for(no_color_flag: bool, no_color_env: Option<&OsStr>, terminal: bool) -> bool {
+    let no_color_env = no_color_env.is_some_and(|value| !value.is_empty());
+    !no_color_flag && !no_color_env && terminal
+}
+
+/// Width of the terminal on stdout, falling back to `COLUMNS`.
+/// `None` when neither is known, e.g. when piping.
+pub fn terminal_width() -> Option<usize> {
+    match terminal_size::terminal_size_of(io::stdout()) {
+        Some((Width(width), _)) => Some(width as usize),
+        None => env::var("COLUMNS").ok()?.parse().ok(),
+    }
+}
+
+/// ASCII stand-ins for the symbols `td` prints. `…` stays one character wide so
+/// truncated rows still fit their width.
+const ASCII_SYMBOLS: &[(char, &str)] = &[
+    ('✓', "[x]"),
+    ('📝', "[N]"),
+    ('⚠', "[!]"),
+    ('🔒', "[B]"),
+    ('🔥', "[old]"),
+    ('…', "."),
+];
+
+/// `s` with the symbols of `ASCII_SYMBOLS` replaced and any other non-ASCII
+/// character shown as `?`, for terminals that can't render Unicode.
+pub fn to_ascii_safe(s: &str) -> String {
+    s.chars()
+        .map(
+            |c| match ASCII_SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+                _ if c.is_ascii() => c.to_string(),
+                Some((_, replacement)) => replacement.to_string(),
+                None => "?".to_string(),
+            },
+        )
+        .collect()
+}
+
+/// Wraps `text` in the given ANSI color code when color is enabled.
+pub fn paint(text: &str, code: u8) -> String {
+    match color_enabled() {
+        true => format!("\x1b[{code}m{text}\x1b[0m"),
+        false => text.to_string(),
+    }
+}
+
+#[test]
+fn test_no_color_env() {
+    let set = Some(OsStr::new("1"));
+    let empty = Some(OsStr::new(""));
+
+    assert!(color_enabled_for(false, None, true));
+    assert!(color_enabled_for(false, empty, true));
+    assert!(!color_enabled_for(false, set, true));
+    assert!(!color_enabled_for(true, None, true));
+    assert!(!color_enabled_for(false, None, false));
+}
+
+#[test]
+fn test_to_ascii_safe() {
+    assert_eq!(to_ascii_safe("⚠ OVERDUE"), "[!] OVERDUE");
+    assert_eq!(to_ascii_safe("✓ Added 📝 🔒"), "[x] Added [N] [B]");
+    assert_eq!(to_ascii_safe("Café"), "Caf?");
+    assert!(to_ascii_safe("A long tas…").is_ascii());
+}
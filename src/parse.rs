@@ -0,0 +1,29 @@
+use anyhow::{Result, anyhow};
+
+/// `FROM-TO` as an inclusive range of task ids, e.g. `5-10`.
+pub fn parse_id_range(s: &str) -> Result<(i64, i64)> {
+    if s.starts_with('-') {
+        return Err(anyhow!("Ids can't be negative, got \"{s}\""));
+    }
+
+    let (from, to) = s
+        .split_once('-')
+        .ok_or_else(|| anyhow!("Expected a range like 5-10, got \"{s}\""))?;
+    let (from, to): (i64, i64) = (from.trim().parse()?, to.trim().parse()?);
+
+    match (from, to) {
+        _ if from < 0 || to < 0 => Err(anyhow!("Ids can't be negative, got \"{s}\"")),
+        _ if from > to => Err(anyhow!("Range starts after it ends, got \"{s}\"")),
+        _ => Ok((from, to)),
+    }
+}
+
+#[test]
+fn test_parse_id_range() {
+    assert_eq!(parse_id_range("5-10").unwrap(), (5, 10));
+    assert_eq!(parse_id_range("7-7").unwrap(), (7, 7));
+
+    for invalid in ["10-5", "-5-10", "5--10", "5", "5-", "a-b", ""] {
+        assert!(parse_id_range(invalid).is_err(), "{invalid}");
+    }
+}
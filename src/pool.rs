@@ -0,0 +1,134 @@
+use crate::db::{self, Status};
+use anyhow::Result;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::path::Path;
+use std::time::Duration;
+
+/// A pool of connections to a task database, to share between threads when
+/// embedding `td`. Mirrors the task operations of `TdContext`.
+#[derive(Clone)]
+pub struct TdPool {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl TdPool {
+    /// Opens the database at `path`, creating and migrating it if needed.
+    pub fn new(path: &Path) -> Result<Self> {
+        // WAL lets readers run alongside a writer, the timeout queues writers
+        let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+            conn.busy_timeout(Duration::from_secs(5))?;
+            conn.pragma_update(None, "journal_mode", "WAL")
+        });
+
+        TdPool::build(Pool::new(manager)?)
+    }
+
+    /// A fresh database that only lives as long as the pool. In-memory
+    /// connections share a cache that locks whole tables, so the pool holds
+    /// just one.
+    pub fn new_in_memory() -> Result<Self> {
+        let manager = SqliteConnectionManager::memory()
+            .with_init(|conn| conn.execute_batch("PRAGMA foreign_keys = ON;"));
+
+        TdPool::build(Pool::builder().max_size(1).build(manager)?)
+    }
+
+    fn build(pool: Pool<SqliteConnectionManager>) -> Result<Self> {
+        db::prepare_db(&*pool.get()?)?;
+        Ok(TdPool { pool })
+    }
+
+    /// A connection of the pool, returned to it once dropped.
+    pub fn get(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        Ok(self.pool.get()?)
+    }
+
+    pub fn add_task(&self, task: &str, priority: Option<i64>) -> Result<i64> {
+        Ok(db::add_task(
+            &*self.get()?,
+            task,
+            priority,
+            None,
+            None,
+            None,
+            None,
+        )?)
+    }
+
+    pub fn start_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&*self.get()?, id, Status::InProgress).map(|_| ())
+    }
+
+    pub fn complete_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&*self.get()?, id, Status::Completed).map(|_| ())
+    }
+
+    pub fn cancel_task(&self, id: i64) -> Result<()> {
+        db::update_task_status(&*self.get()?, id, Status::Cancelled).map(|_| ())
+    }
+
+    pub fn delete_task(&self, id: i64) -> Result<()> {
+        db::delete_task(&*self.get()?, id).map(|_| ())
+    }
+
+    /// The text of a task, `None` when there is no task with the id.
+    pub fn task_text(&self, id: i64) -> Result<Option<String>> {
+        Ok(db::get_task_by_id(&*self.get()?, id)?.map(|task| task.task))
+    }
+
+    /// Ids of tasks pending for at least `threshold_days`.
+    pub fn stale_task_ids(&self, threshold_days: u32) -> Result<Vec<i64>> {
+        let tasks = db::list_stale_tasks(&*self.get()?, threshold_days)?;
+        Ok(tasks.iter().map(|task| task.id).collect())
+    }
+
+    /// Number of tasks of any status, leaving out deleted ones.
+    pub fn count_tasks(&self) -> Result<usize> {
+        Ok(db::count_by_status(&*self.get()?)?.values().sum())
+    }
+
+    /// Number of tasks with the status, leaving out deleted ones.
+    pub fn count_with_status(&self, status: Status) -> Result<usize> {
+        Ok(*db::count_by_status(&*self.get()?)?
+            .get(&status)
+            .unwrap_or(&0))
+    }
+}
+
+#[test]
+fn test_pool_across_threads() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let pool = TdPool::new(&dir.path().join("tasks.db")).unwrap();
+
+    let handles: Vec<_> = (0..10)
+        .map(|i| {
+            let pool = pool.clone();
+            std::thread::spawn(move || pool.add_task(&format!("Task {i}"), None).unwrap())
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(pool.count_tasks().unwrap(), 10);
+}
+
+#[test]
+fn test_pool_in_memory() {
+    let pool = TdPool::new_in_memory().unwrap();
+
+    let id = pool.add_task("First", Some(5)).unwrap();
+    pool.add_task("Second", None).unwrap();
+    pool.start_task(id).unwrap();
+
+    assert_eq!(pool.task_text(id).unwrap().as_deref(), Some("First"));
+    assert_eq!(pool.count_with_status(Status::InProgress).unwrap(), 1);
+    assert_eq!(pool.count_with_status(Status::Pending).unwrap(), 1);
+    assert_eq!(pool.count_with_status(Status::Blocked).unwrap(), 0);
+    assert_eq!(pool.stale_task_ids(0).unwrap().len(), 1);
+
+    pool.delete_task(id).unwrap();
+    assert_eq!(pool.count_tasks().unwrap(), 1);
+}
@@ -0,0 +1,64 @@
+use std::io::{self, IsTerminal, Write};
+
+/// Confirmations are only asked on a terminal. In a script there is nobody to
+/// answer, so instead of guessing `td` exits with an error pointing at the flag
+/// that confirms up front.
+fn require_terminal(prompt: &str, skip_flag: &str) {
+    if !io::stdin().is_terminal() {
+        eprintln!(
+            "{prompt} Not asking, stdin is not a terminal. Hint: pass {skip_flag} to confirm"
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Asks a yes or no question, anything but `y` or `yes` is a no.
+/// Exits when stdin is not a terminal, see `require_terminal`.
+pub fn confirm(prompt: &str, skip_flag: &str) -> bool {
+    require_terminal(prompt, skip_flag);
+    print!("{prompt} [y/N] ");
+    io::stdout().flush().ok();
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok();
+
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// Asks a yes or no question defaulting to yes, only `n` or `no` is a no.
+/// Running out of stdin is a no as well. Exits when stdin is not a terminal.
+pub fn confirm_default_yes(prompt: &str, skip_flag: &str) -> bool {
+    require_terminal(prompt, skip_flag);
+    print!("{prompt} [Y/n] ");
+    io::stdout().flush().ok();
+
+    let mut answer = String::new();
+    if let Ok(0) | Err(_) = io::stdin().read_line(&mut answer) {
+        return false;
+    }
+
+    !matches!(answer.trim(), "n" | "N" | "no")
+}
+
+/// Asks until `parse` accepts the answer, `None` once stdin runs out.
+pub fn prompt<T>(label: &str, parse: impl Fn(&str) -> Result<T, String>) -> Option<T> {
+    loop {
+        print!("{label}: ");
+        io::stdout().flush().ok();
+
+        let mut answer = String::new();
+        if let Ok(0) | Err(_) = io::stdin().read_line(&mut answer) {
+            return None;
+        }
+
+        match parse(answer.trim()) {
+            Ok(value) => return Some(value),
+            Err(err) => println!("{err}"),
+        }
+    }
+}
+
+/// Empty answers are `None`.
+pub fn optional(answer: &str) -> Option<String> {
+    Some(answer.to_string()).filter(|answer| !answer.is_empty())
+}
@@ -0,0 +1,60 @@
+/// Edits allowed before a candidate is no longer considered a typo of the input.
+const MAX_DISTANCE: usize = 2;
+
+/// Number of single character insertions, deletions or substitutions
+/// needed to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev + usize::from(ca != *cb);
+            prev = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(prev + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Finds the candidate closest to `input`, ties going to the earlier candidate.
+pub fn closest_command<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(input, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[test]
+fn test_levenshtein() {
+    assert_eq!(levenshtein("list", "list"), 0);
+    assert_eq!(levenshtein("lst", "list"), 1);
+    assert_eq!(levenshtein("lisst", "list"), 1);
+    assert_eq!(levenshtein("lust", "list"), 1);
+    assert_eq!(levenshtein("", "list"), 4);
+}
+
+#[test]
+fn test_closest_command() {
+    let commands = ["add", "list", "done", "next", "show"];
+
+    assert_eq!(closest_command("lst", &commands), Some("list"));
+    assert_eq!(closest_command("dne", &commands), Some("done"));
+    assert_eq!(closest_command("nxt", &commands), Some("next"));
+    assert_eq!(closest_command("shwo", &commands), Some("show"));
+}
+
+#[test]
+fn test_closest_command_no_match() {
+    let commands = ["add", "list", "done", "next", "show"];
+
+    assert_eq!(closest_command("archive", &commands), None);
+    assert_eq!(closest_command("xyzzy", &commands), None);
+    assert_eq!(closest_command("lst", &[]), None);
+}
@@ -0,0 +1,303 @@
+use crate::db::{self, ListOptions, Status, Task};
+use anyhow::Error;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use rusqlite::Connection;
+use std::result;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Done,
+    Next,
+    Cancel,
+    Edit,
+    Quit,
+}
+
+impl Action {
+    fn from_key(key: KeyCode) -> Option<Action> {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => Some(Action::Up),
+            KeyCode::Down | KeyCode::Char('j') => Some(Action::Down),
+            KeyCode::Char('d') => Some(Action::Done),
+            KeyCode::Char('n') => Some(Action::Next),
+            KeyCode::Char('c') => Some(Action::Cancel),
+            KeyCode::Char('e') => Some(Action::Edit),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    Browse,
+    /// Editing the selected task text, holding the text typed so far
+    Edit(String),
+}
+
+/// State of `list --interactive`, the tasks are re-read after every change.
+pub struct App {
+    opts: ListOptions,
+    tasks: Vec<Task>,
+    table: TableState,
+    mode: Mode,
+    message: Option<String>,
+    running: bool,
+}
+
+impl App {
+    pub fn new(conn: &Connection, opts: ListOptions) -> result::Result<App, Error> {
+        let mut app = App {
+            opts,
+            tasks: vec![],
+            table: TableState::default().with_selected(0),
+            mode: Mode::Browse,
+            message: None,
+            running: true,
+        };
+        app.refresh(conn)?;
+        Ok(app)
+    }
+
+    fn refresh(&mut self, conn: &Connection) -> result::Result<(), Error> {
+        self.tasks = db::query_tasks(conn, &self.opts)?;
+
+        let last = self.tasks.len().saturating_sub(1);
+        self.table.select(Some(self.selected().min(last)));
+        Ok(())
+    }
+
+    fn selected(&self) -> usize {
+        self.table.selected().unwrap_or_default()
+    }
+
+    fn selected_task(&self) -> Option<&Task> {
+        self.tasks.get(self.selected())
+    }
+
+    fn set_selected_status(
+        &mut self,
+        conn: &Connection,
+        status: Status,
+    ) -> result::Result<(), Error> {
+        if let Some(task) = self.selected_task() {
+            db::update_task_status(conn, task.id, status)?;
+            self.refresh(conn)?;
+        }
+        Ok(())
+    }
+
+    pub fn dispatch(&mut self, conn: &Connection, action: Action) -> result::Result<(), Error> {
+        match action {
+            Action::Up => self.table.select(Some(self.selected().saturating_sub(1))),
+            Action::Down => {
+                let last = self.tasks.len().saturating_sub(1);
+                self.table.select(Some((self.selected() + 1).min(last)))
+            }
+            Action::Done => self.set_selected_status(conn, Status::Completed)?,
+            Action::Next => self.set_selected_status(conn, Status::InProgress)?,
+            Action::Cancel => self.set_selected_status(conn, Status::Cancelled)?,
+            Action::Edit => {
+                if let Some(task) = self.selected_task() {
+                    self.mode = Mode::Edit(task.task.clone())
+                }
+            }
+            Action::Quit => self.running = false,
+        }
+        Ok(())
+    }
+
+    fn handle_edit_key(&mut self, conn: &Connection, key: KeyCode) -> result::Result<(), Error> {
+        let Mode::Edit(text) = &mut self.mode else {
+            return Ok(());
+        };
+
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => {
+                let text = text.trim().to_string();
+                self.mode = Mode::Browse;
+
+                if let (Some(task), false) = (self.selected_task(), text.is_empty()) {
+                    db::rename_task(conn, task.id, &text)?;
+                    self.refresh(conn)?;
+                }
+            }
+            KeyCode::Esc => self.mode = Mode::Browse,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn handle_key(&mut self, conn: &Connection, key: KeyCode) {
+        let result = match (&self.mode, Action::from_key(key)) {
+            (Mode::Edit(_), _) => self.handle_edit_key(conn, key),
+            (Mode::Browse, Some(action)) => self.dispatch(conn, action),
+            (Mode::Browse, None) => Ok(()),
+        };
+
+        // Errors are shown in the footer, the session carries on
+        self.message = result.err().map(|err| err.to_string());
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [body, footer] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+    let rows = app.tasks.iter().map(|task| {
+        Row::new(vec![
+            task.id.to_string(),
+            task.status.to_string(),
+            task.priority.to_string(),
+            task.task.clone(),
+        ])
+    });
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(11),
+        Constraint::Length(4),
+        Constraint::Fill(1),
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(Row::new(["ID", "STATUS", "PRIO", "TASK"]).style(Style::new().bold()))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" td "));
+
+    frame.render_stateful_widget(table, body, &mut app.table);
+
+    let help = match (&app.mode, &app.message) {
+        (Mode::Edit(text), _) => format!("Edit: {text}▏ (enter to save, esc to discard)"),
+        (Mode::Browse, Some(message)) => message.clone(),
+        (Mode::Browse, None) => "↑/↓ move  d done  n next  c cancel  e edit  q quit".to_string(),
+    };
+
+    frame.render_widget(Paragraph::new(help), footer);
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    conn: &Connection,
+    app: &mut App,
+) -> result::Result<(), Error> {
+    while app.running {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.handle_key(conn, key.code);
+        }
+    }
+    Ok(())
+}
+
+pub fn run(conn: &Connection, opts: ListOptions) -> result::Result<(), Error> {
+    let mut app = App::new(conn, opts)?;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, conn, &mut app);
+    ratatui::restore();
+
+    result
+}
+
+#[cfg(test)]
+use ratatui::{Terminal, backend::TestBackend};
+
+#[cfg(test)]
+fn test_app() -> (Connection, App) {
+    let conn = db::init_test_db();
+
+    for (task, priority) in [("First", 5), ("Second", 3), ("Third", 1)] {
+        db::add_task(&conn, task, Some(priority), None, None, None, None).unwrap();
+    }
+
+    let app = App::new(&conn, ListOptions::default()).unwrap();
+    (conn, app)
+}
+
+#[test]
+fn test_navigation_is_clamped() {
+    let (conn, mut app) = test_app();
+
+    app.handle_key(&conn, KeyCode::Up);
+    assert_eq!(app.selected(), 0);
+
+    for _ in 0..5 {
+        app.handle_key(&conn, KeyCode::Down);
+    }
+    assert_eq!(app.selected(), 2);
+
+    app.handle_key(&conn, KeyCode::Char('k'));
+    assert_eq!(app.selected_task().unwrap().task, "Second");
+}
+
+#[test]
+fn test_status_actions() {
+    let (conn, mut app) = test_app();
+
+    app.handle_key(&conn, KeyCode::Char('n'));
+    assert_eq!(app.tasks[0].status, Status::InProgress);
+
+    // The status reads as it does in `list` and `show`
+    let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+    terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("In Progress"), "{screen}");
+
+    app.handle_key(&conn, KeyCode::Char('d'));
+    assert_eq!(app.tasks.len(), 2);
+    assert_eq!(app.selected_task().unwrap().task, "Second");
+
+    app.handle_key(&conn, KeyCode::Down);
+    app.handle_key(&conn, KeyCode::Char('c'));
+    assert_eq!(app.tasks.len(), 1);
+    assert_eq!(app.selected(), 0);
+
+    let status = |id| db::get_task_by_id(&conn, id).unwrap().unwrap().status;
+    assert_eq!(status(1), Status::Completed);
+    assert_eq!(status(3), Status::Cancelled);
+}
+
+#[test]
+fn test_edit_mode() {
+    let (conn, mut app) = test_app();
+
+    app.handle_key(&conn, KeyCode::Char('e'));
+    assert_eq!(app.mode, Mode::Edit("First".to_string()));
+
+    // Action keys are typed as text while editing
+    for key in [KeyCode::Backspace, KeyCode::Char('s'), KeyCode::Char('q')] {
+        app.handle_key(&conn, key);
+    }
+    assert!(app.running);
+
+    app.handle_key(&conn, KeyCode::Enter);
+    assert_eq!(app.mode, Mode::Browse);
+    assert_eq!(app.tasks[0].task, "Firssq");
+
+    app.handle_key(&conn, KeyCode::Char('e'));
+    app.handle_key(&conn, KeyCode::Esc);
+    assert_eq!(app.tasks[0].task, "Firssq");
+
+    app.handle_key(&conn, KeyCode::Char('q'));
+    assert!(!app.running);
+}
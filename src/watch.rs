@@ -0,0 +1,90 @@
+use crate::db::{self, ListOptions};
+use crate::output;
+use anyhow::Error;
+use rusqlite::Connection;
+use std::io::{self, Write};
+use std::result;
+use std::thread;
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// What `watch` compares to tell whether the database changed. `MAX(rowid)` and
+/// `COUNT(*)` catch added and removed tasks, `data_version` any commit made by
+/// another connection, e.g. a status change from another `td`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Snapshot {
+    max_rowid: Option<i64>,
+    count: i64,
+    data_version: i64,
+}
+
+fn snapshot(conn: &Connection) -> rusqlite::Result<Snapshot> {
+    let (max_rowid, count) =
+        conn.query_row("SELECT MAX(rowid), COUNT(*) FROM tasks", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    let data_version = conn.pragma_query_value(None, "data_version", |row| row.get(0))?;
+
+    Ok(Snapshot {
+        max_rowid,
+        count,
+        data_version,
+    })
+}
+
+fn redraw(conn: &Connection) -> result::Result<(), Error> {
+    let mut out = io::stdout().lock();
+    let opts = ListOptions::default().width(output::terminal_width());
+
+    write!(out, "{CLEAR_SCREEN}")?;
+    db::list_tasks(conn, &opts, &mut out);
+    Ok(out.flush()?)
+}
+
+/// Re-prints `td list` whenever the database changes, checking every `interval`.
+/// Runs until interrupted, showing the cursor again on Ctrl-C.
+pub fn run(conn: &Connection, interval: Duration) -> result::Result<(), Error> {
+    ctrlc::set_handler(|| {
+        print!("{SHOW_CURSOR}");
+        let _ = io::stdout().flush();
+        std::process::exit(130);
+    })?;
+    print!("{HIDE_CURSOR}");
+
+    let mut last = None;
+    loop {
+        let current = snapshot(conn)?;
+        if last != Some(current) {
+            redraw(conn)?;
+            last = Some(current);
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[test]
+fn test_snapshot_detects_changes() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("td.db");
+
+    let watcher = Connection::open(&path).unwrap();
+    db::prepare_db(&watcher).unwrap();
+    let other = Connection::open(&path).unwrap();
+
+    let empty = snapshot(&watcher).unwrap();
+    assert_eq!(snapshot(&watcher).unwrap(), empty);
+
+    let id = db::add_task(&other, "Task", None, None, None, None, None).unwrap();
+    let added = snapshot(&watcher).unwrap();
+    assert_ne!(added, empty);
+    assert_eq!(added.max_rowid, Some(id));
+    assert_eq!(added.count, 1);
+
+    db::mark_task_done(&other, id);
+    let completed = snapshot(&watcher).unwrap();
+    assert_ne!(completed, added);
+    assert_eq!(snapshot(&watcher).unwrap(), completed);
+}
@@ -0,0 +1,619 @@
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// Runs `td` against a database in a fresh home directory.
+fn td(home: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("td").unwrap();
+    cmd.env("HOME", home.path()).env("NO_COLOR", "1");
+    cmd
+}
+
+#[test]
+fn test_count_only_empty() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .code(1)
+        .stdout("0\n");
+}
+
+#[test]
+fn test_count_only_with_tasks() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "First"]).assert().success();
+    td(&home).args(["add", "Second"]).assert().success();
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .code(0)
+        .stdout("2\n");
+
+    td(&home)
+        .args(["list", "--count-only", "--completed"])
+        .assert()
+        .code(1)
+        .stdout("0\n");
+}
+
+#[test]
+fn test_fail_on_empty() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["list", "--fail-on-empty"]).assert().code(1);
+    td(&home).args(["list"]).assert().success();
+
+    td(&home).args(["add", "First"]).assert().success();
+
+    td(&home)
+        .args(["list", "--fail-on-empty", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("1 First\n");
+}
+
+#[test]
+fn test_describe() {
+    let home = TempDir::new().unwrap();
+
+    // Invalid answers are asked again
+    td(&home)
+        .arg("describe")
+        .write_stdin("\nWrite report\n9\n4\n31.02.2026\n01.03.2026\nwork, urgent\n")
+        .assert()
+        .success();
+
+    td(&home)
+        .args(["list", "--format", "compact"])
+        .assert()
+        .stdout(predicates::str::contains("1|Pending|!|"))
+        .stdout(predicates::str::contains(
+            "|2026-03-01|Write report|work,urgent\n",
+        ));
+}
+
+#[test]
+fn test_describe_aborts_on_eof() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .arg("describe")
+        .write_stdin("Write report\n")
+        .assert()
+        .stdout(predicates::str::ends_with("Aborted.\n"));
+
+    td(&home).args(["list", "--count-only"]).assert().code(1);
+}
+
+#[test]
+fn test_completions_install() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["completions", "zsh", "--install"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("does not exist"));
+
+    std::fs::create_dir_all(home.path().join(".zsh/completions")).unwrap();
+
+    td(&home)
+        .args(["completions", "--install"])
+        .env("SHELL", "/bin/zsh")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("Installed zsh completions"));
+
+    let script = std::fs::read_to_string(home.path().join(".zsh/completions/_td")).unwrap();
+    assert!(script.starts_with("#compdef td"));
+}
+
+#[test]
+fn test_cancel_confirmed() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "Write report"]).assert().success();
+
+    td(&home)
+        .args(["cancel", "1", "--yes"])
+        .assert()
+        .success()
+        .stdout("Cancelled task 1\n");
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .code(1)
+        .stdout("0\n");
+}
+
+// Tests run with piped stdin, which is not a terminal, so none of them can
+// answer a prompt. Without --yes the commands refuse instead of asking.
+#[test]
+fn test_cancel_without_terminal() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "Write report"]).assert().success();
+
+    td(&home)
+        .args(["cancel", "1"])
+        .write_stdin("y\n")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::contains(
+            "stdin is not a terminal. Hint: pass --yes",
+        ));
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .success()
+        .stdout("1\n");
+}
+
+#[test]
+fn test_bulk_done_without_terminal() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "First"]).assert().success();
+    td(&home).args(["add", "Second"]).assert().success();
+
+    td(&home)
+        .args(["done", "1", "2"])
+        .write_stdin("y\n")
+        .assert()
+        .code(1)
+        .stderr(predicates::str::starts_with("Complete 2 tasks? Not asking"));
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .success()
+        .stdout("2\n");
+
+    td(&home)
+        .args(["done", "1", "2", "--yes"])
+        .assert()
+        .success();
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .code(1)
+        .stdout("0\n");
+}
+
+#[test]
+fn test_gc_without_terminal() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "First"]).assert().success();
+    td(&home).args(["cancel", "1", "-y"]).assert().success();
+
+    td(&home)
+        .args(["gc"])
+        .write_stdin("y\n")
+        .assert()
+        .code(1)
+        .stdout("");
+
+    td(&home)
+        .args(["gc", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Deleted 1 tasks."));
+}
+
+#[test]
+fn test_pause_all_without_terminal() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["pause", "--all"])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("Hint: pass --force"));
+}
+
+#[test]
+fn test_due_from_never() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "Write report"]).assert().success();
+
+    td(&home)
+        .args(["due", "1", "15.10.2025"])
+        .write_stdin("\n")
+        .assert()
+        .code(1)
+        .stderr(predicates::str::starts_with(
+            "Task [1]: due date Never → 2025-10-15. Confirm? Not asking",
+        ));
+
+    td(&home)
+        .args(["due", "1", "15.10.2025", "--yes"])
+        .assert()
+        .success()
+        .stdout("Task [1]: due date Never → 2025-10-15.\n");
+
+    td(&home)
+        .args(["due", "1", "20.10.2025", "--yes"])
+        .assert()
+        .success()
+        .stdout("Task [1]: due date 2025-10-15 → 2025-10-20.\n");
+}
+
+#[test]
+fn test_list_no_in_progress() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "First"]).assert().success();
+    td(&home).args(["add", "Second"]).assert().success();
+    td(&home).args(["next", "--id", "1"]).assert().success();
+
+    td(&home)
+        .args(["list", "--no-in-progress", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("2 Second\n");
+}
+
+#[test]
+fn test_show_field() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["show", "--field", "status"])
+        .assert()
+        .code(1)
+        .stdout("");
+
+    td(&home).args(["add", "Write report"]).assert().success();
+    td(&home).args(["next"]).assert().success();
+
+    td(&home)
+        .args(["show", "--field", "status"])
+        .assert()
+        .success()
+        .stdout("In Progress\n");
+    td(&home)
+        .args(["show", "--field", "task"])
+        .assert()
+        .success()
+        .stdout("Write report\n");
+    td(&home)
+        .args(["show", "--field", "id"])
+        .assert()
+        .success()
+        .stdout("1\n");
+    td(&home)
+        .args(["show", "--field", "colour"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_list_with_scores() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "First", "-p", "5"])
+        .assert()
+        .success();
+    td(&home).args(["add", "Second task"]).assert().success();
+
+    let output = td(&home).args(["list", "--with-scores"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+
+    assert!(lines.next().unwrap().ends_with(" SCORE"));
+    for line in lines {
+        let score = line.rsplit(' ').next().unwrap();
+        assert!(score.parse::<f64>().is_ok(), "{line}");
+        assert_eq!(score.split('.').nth(1).map(str::len), Some(2));
+    }
+}
+
+#[test]
+fn test_list_priority_symbol() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "First", "-p", "4"])
+        .assert()
+        .success();
+    td(&home)
+        .args(["add", "Second", "-p", "1"])
+        .assert()
+        .success();
+
+    td(&home)
+        .args(["list", "--priority=!", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("1 First\n");
+    td(&home)
+        .args(["list", "--priority", "1", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("2 Second\n");
+    td(&home).args(["list", "--priority=?"]).assert().failure();
+}
+
+#[test]
+fn test_list_fields() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "Some task", "--due", "1.1.2030"])
+        .assert()
+        .success();
+
+    let header_columns = |fields: &str| {
+        let output = td(&home)
+            .args(["list", "--fields", fields])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let header = stdout.lines().next().unwrap().to_string();
+        header.split_whitespace().collect::<Vec<_>>().join(" ")
+    };
+
+    assert_eq!(header_columns("id,task"), "ID TASK");
+    assert_eq!(header_columns("id,task,due"), "ID DUE TASK");
+
+    td(&home)
+        .args(["list", "--fields", "id,due"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("must include id and task"));
+    td(&home)
+        .args(["list", "--fields", "id,task,size"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("expected one of id, status"));
+    td(&home)
+        .args(["list", "--fields", "id,task,due,ID"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("\"id\" is listed twice"));
+}
+
+#[test]
+fn test_add_priority_symbol() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "Task", "--priority-symbol=!"])
+        .assert()
+        .success();
+
+    td(&home)
+        .args(["list", "--priority", "4", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("1 Task\n");
+
+    td(&home)
+        .args(["add", "Task", "--priority-symbol=4"])
+        .assert()
+        .failure();
+    td(&home)
+        .args(["add", "Task", "-p", "2", "--priority-symbol=!"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_dry_run() {
+    let home = TempDir::new().unwrap();
+
+    td(&home).args(["add", "First"]).assert().success();
+
+    td(&home)
+        .args(["add", "Second", "--dry-run"])
+        .assert()
+        .success()
+        .stdout("[dry-run] Would: add task \"Second\"\n");
+    td(&home)
+        .args(["--dry-run", "done", "1"])
+        .assert()
+        .success()
+        .stdout("[dry-run] Would: mark task [1] complete\n");
+    td(&home)
+        .args(["cancel", "1", "--dry-run"])
+        .assert()
+        .code(2);
+
+    td(&home)
+        .args(["list", "--count-only"])
+        .assert()
+        .success()
+        .stdout("1\n");
+    td(&home)
+        .args(["list", "--completed", "--count-only"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_list_due_format() {
+    let home = TempDir::new().unwrap();
+    let due = chrono::Local::now().date_naive() + chrono::Days::new(3);
+
+    td(&home)
+        .args(["add", "Task", "--due", &due.format("%d.%m.%Y").to_string()])
+        .assert()
+        .success();
+
+    let due_column = |format: &str| {
+        let output = td(&home)
+            .args(["list", "--fields", "id,due,task", "--due-format", format])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let row = stdout.lines().nth(1).unwrap().to_string();
+        // ID is padded to 4 characters and DUE to 11, each followed by a space
+        row[5..16].trim().to_string()
+    };
+
+    assert_eq!(due_column("absolute"), due.format("%Y-%m-%d").to_string());
+    assert_eq!(due_column("relative"), "in 3d");
+}
+
+#[test]
+fn test_list_mine() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "Theirs", "--assignee", "bob"])
+        .assert()
+        .success();
+    td(&home)
+        .args(["add", "Mine", "--assignee", "alice"])
+        .assert()
+        .success();
+
+    td(&home)
+        .args(["list", "--mine"])
+        .env_remove("TD_USER")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::contains("No user configured"));
+    td(&home)
+        .args(["list", "--mine", "--format", "minimal"])
+        .env("TD_USER", "alice")
+        .assert()
+        .success()
+        .stdout("2 Mine\n");
+}
+
+#[test]
+fn test_config_set_and_get() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["config", "get", "default_priority"])
+        .assert()
+        .success()
+        .stdout("3\n");
+
+    td(&home)
+        .args(["config", "set", "default_priority", "5"])
+        .assert()
+        .success();
+    td(&home)
+        .args(["config", "get", "default_priority"])
+        .assert()
+        .success()
+        .stdout("5\n");
+    td(&home)
+        .args(["config", "get", "default_priority"])
+        .env("TD_DEFAULT_PRIORITY", "2")
+        .assert()
+        .success()
+        .stdout("2\n");
+
+    td(&home).args(["add", "Task"]).assert().success();
+    td(&home)
+        .args(["list", "--priority", "5", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("1 Task\n");
+
+    td(&home)
+        .args(["config", "get", "color"])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains(
+            "expected one of stale_after_hours",
+        ));
+}
+
+#[test]
+fn test_done_range() {
+    let home = TempDir::new().unwrap();
+
+    for task in ["First", "Second", "Third", "Fourth", "Fifth", "Sixth"] {
+        td(&home).args(["add", task]).assert().success();
+    }
+
+    td(&home)
+        .args(["done", "1-5", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Marked task [5] complete"));
+    td(&home)
+        .args(["list", "--completed", "--format", "minimal"])
+        .assert()
+        .success()
+        .stdout("1 First\n2 Second\n3 Third\n4 Fourth\n5 Fifth\n");
+
+    td(&home)
+        .args(["done", "5-8", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicates::str::ends_with("Skipped tasks 5, 7, 8\n"));
+    td(&home).args(["list", "--count-only"]).assert().code(1);
+
+    td(&home).args(["done", "8-5", "--yes"]).assert().failure();
+    td(&home)
+        .args(["done", "1-1000000000", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("at most 10000 ids"));
+}
+
+#[test]
+fn test_list_ascii() {
+    let home = TempDir::new().unwrap();
+
+    td(&home)
+        .args(["add", "Café meeting", "--due", "1.1.2020"])
+        .assert()
+        .success();
+    td(&home)
+        .args(["add", "A task long enough to be truncated"])
+        .assert()
+        .success();
+    td(&home)
+        .args(["add", "Checks ✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓✓", "--tags", "✓"])
+        .assert()
+        .success();
+
+    let output = td(&home)
+        .args(["list", "--ascii", "--width", "75"])
+        .output()
+        .unwrap();
+
+    assert!(output.stdout.iter().all(|&byte| byte <= 127));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().all(|line| line.len() <= 75), "{stdout}");
+    assert!(stdout.contains("\"Checks [x][x]"));
+    assert!(stdout.starts_with("[!] OVERDUE"));
+    assert!(stdout.contains("Caf? meeting"));
+}
+
+#[test]
+fn test_import_export_todotxt() {
+    let home = TempDir::new().unwrap();
+    let todo = "\
+(A) 2025-09-01 Write report @work due:2025-09-15
+x 2025-09-05 2025-09-03 Call plumber pri:C
+";
+    let path = home.path().join("todo.txt");
+    std::fs::write(&path, todo).unwrap();
+
+    td(&home)
+        .args(["import", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("Imported 2 tasks.\n");
+
+    td(&home).args(["export"]).assert().success().stdout(todo);
+}